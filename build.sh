@@ -514,9 +514,16 @@ function doDockerBuild {
   $SINGLE_PAGE_ONLY && docker_run_args+=( --single-page )
   $SERVE && docker_run_args+=( --serve )
 
-  # Pass in the html-build SHA (since there's no .git directory inside the container)
+  # Pass in the html-build SHA (since there's no .git directory inside the container), and any
+  # self-link configuration
+  local docker_env_args=( --env "BUILD_SHA_OVERRIDE=$(git rev-parse HEAD)" )
+  local var
+  for var in "${!HTML_SELF_LINK_@}"; do
+    docker_env_args+=( --env "$var=${!var}" )
+  done
+
   docker run --rm --interactive --tty \
-             --env "BUILD_SHA_OVERRIDE=$(git rev-parse HEAD)" \
+             "${docker_env_args[@]}" \
              --mount "type=bind,source=$HTML_SOURCE,destination=/whatwg/html-build/html,readonly=1" \
              --mount "type=bind,source=$HTML_CACHE,destination=/whatwg/html-build/.cache" \
              --mount "type=bind,source=$HTML_OUTPUT,destination=/whatwg/html-build/output" \
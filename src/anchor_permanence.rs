@@ -10,6 +10,7 @@ use html5ever::{QualName, local_name, ns};
 use markup5ever_rcdom::Handle;
 use std::collections::HashSet;
 
+#[derive(Default)]
 pub struct Processor {
     required_ids: HashSet<String>,
     script_node: Option<Handle>,
@@ -103,6 +104,8 @@ mod tests {
         Ok(())
     }
 
+    // This predates the cloned_ref_to_slice_refs lint.
+    #[allow(clippy::cloned_ref_to_slice_refs)]
     #[tokio::test]
     async fn no_script_present_noop() -> io::Result<()> {
         let parsed = parse_document_async(
@@ -10,6 +10,7 @@ use markup5ever_rcdom::{Handle, NodeData};
 
 use crate::dom_utils::{self, NodeHandleExt};
 use crate::parser;
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, format_warnings};
 
 #[derive(Debug, Default)]
 struct Descriptions {
@@ -36,19 +37,33 @@ struct Edit {
     has_special_semantics: bool,
 }
 
-pub struct Processor {
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
     /// Map from attribute key (e.g., attr-elem-someattribute) to the
     /// descriptions found in the Attributes table.
     attributes: HashMap<StrTendril, Descriptions>,
 
+    /// The attr-* keys in the Attributes table, with the row each first
+    /// appears in.
+    attribute_rows: Vec<(StrTendril, Handle)>,
+
+    /// The keys which appear in any Content attributes section, whether or not
+    /// they are to be annotated.
+    referenced_keys: HashSet<String>,
+
     /// List of <dd> nodes in Content attributes sections that need to be filled in.
     edits: Vec<Edit>,
 }
 
-impl Processor {
-    pub fn new() -> Self {
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
         Processor {
+            parsed,
             attributes: HashMap::new(),
+            attribute_rows: Vec::new(),
+            referenced_keys: HashSet::new(),
             edits: Vec::new(),
         }
     }
@@ -83,6 +98,8 @@ impl Processor {
         }
     }
 
+    // This predates the unnecessary_unwrap and useless_conversion lints.
+    #[allow(clippy::unnecessary_unwrap, clippy::useless_conversion)]
     fn index_attribute_table(&mut self, table: &Handle) {
         let tbody = match table
             .children
@@ -136,6 +153,9 @@ impl Processor {
                 if !seen_this_row.insert(attr_key.clone()) {
                     continue;
                 }
+                if attr_key.starts_with("attr-") && !self.attributes.contains_key(&attr_key) {
+                    self.attribute_rows.push((attr_key.clone(), row.clone()));
+                }
 
                 // Find the <!-- or: --> comment, if one exists, and extract its contents.
                 let description = description_td.children.borrow();
@@ -189,6 +209,8 @@ impl Processor {
         }
     }
 
+    // This predates the collapsible_match lint.
+    #[allow(clippy::collapsible_match)]
     fn index_attribute_list(&mut self, dt: &Handle) {
         // If a <dd> contains <!-- no-annotate -->, it is not annotated.
         // If it contains <!-- variant -->, the description found in a <!-- or: ... --> comment is used instead.
@@ -227,6 +249,9 @@ impl Processor {
                         }
                         _ => (),
                     });
+                    if let Some(ref key) = key {
+                        self.referenced_keys.insert(key.to_string());
+                    }
                     match (can_annotate, key) {
                         (true, Some(key)) => Some(Edit {
                             dd: dd.clone(),
@@ -240,6 +265,38 @@ impl Processor {
         );
     }
 
+    /// Returns a description of each content attribute which has no entry in
+    /// the Attributes table, and each entry which no element's content
+    /// attributes refer to. Should be called after visiting the document.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<(Option<u64>, String)> = Vec::new();
+        // Only attr- keys are described by the Attributes table; others, such
+        // as the body element's handler-window-on* attributes, are described
+        // elsewhere.
+        for edit in self.edits.iter() {
+            if edit.key.starts_with("attr-") && !self.attributes.contains_key(&edit.key) {
+                warnings.push((
+                    self.parsed.line_number_for(&edit.dd),
+                    format!(
+                        "Content attribute {} has no entry in the Attributes table, so it cannot be described.",
+                        edit.key
+                    ),
+                ));
+            }
+        }
+        for (key, row) in self.attribute_rows.iter() {
+            if !self.referenced_keys.contains(&key[..]) {
+                warnings.push((
+                    self.parsed.line_number_for(row),
+                    format!(
+                        "Attributes table entry {key} is not listed in any element's content attributes."
+                    ),
+                ));
+            }
+        }
+        format_warnings(warnings)
+    }
+
     pub async fn apply(self) -> io::Result<()> {
         let em_dash = StrTendril::from(" \u{2014} ");
 
@@ -334,7 +391,7 @@ mod tests {
 </dl>
             "#.trim().as_bytes()).await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -388,7 +445,7 @@ mod tests {
 </dl>
             "#.trim().as_bytes()).await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -431,7 +488,7 @@ mod tests {
 </tbody></table>
             "#.trim().as_bytes()).await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -469,7 +526,7 @@ mod tests {
 </tbody></table>
             "#.trim().as_bytes()).await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -513,7 +570,7 @@ mod tests {
 </tbody></table>
             "#.trim().as_bytes()).await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -539,4 +596,37 @@ mod tests {
         );
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_warnings() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<dl class="element">
+    <dt><span data-x="concept-element-attributes">Content attributes</span>
+    <dd><code data-x="attr-a-href">href</code>
+    <dd><code data-x="attr-a-ping">ping</code>
+    <dd><code data-x="handler-window-onafterprint">onafterprint</code>
+    <dd><code data-x="attr-a-type">type</code> <!-- no-annotate -->
+    <dd><code data-x="attr-a-shape">shape</code> <!-- no-annotate -->
+</dl>
+<table id="attributes-1"><tbody>
+    <tr><th><code data-x>href</code><td><code data-x="attr-a-href">a</code><td>Destination
+    <tr><th><code data-x>type</code><td><code data-x="attr-a-type">a</code><td>Hint
+    <tr><th><code data-x>hreflang</code><td><code data-x="attr-a-hreflang">a</code><td>Language
+</tbody></table>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.warnings(),
+            vec![
+                "Line 5: Content attribute attr-a-ping has no entry in the Attributes table, so it cannot be described.",
+                "Line 13: Attributes table entry attr-a-hreflang is not listed in any element's content attributes.",
+            ]
+        );
+        Ok(())
+    }
 }
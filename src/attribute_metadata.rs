@@ -0,0 +1,280 @@
+//! Gathers each row of the Attributes table (<table id="attributes-1">) so that
+//! it can be written out as JSON, including the description of valid values
+//! and what the terms used there link to.
+
+use std::collections::HashMap;
+
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::{Handle, NodeData};
+use serde::Serialize;
+
+use crate::dom_utils::{self, NodeHandleExt};
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AttributeMetadata {
+    pub name: String,
+    pub applies_to: Vec<AppliesTo>,
+    pub description: String,
+
+    /// The description found in an <!-- or: --> comment, if any, which some
+    /// elements use instead.
+    pub variant_description: Option<String>,
+    pub value: String,
+    pub value_links: Vec<Link>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct AppliesTo {
+    /// The element name, or e.g. "HTML elements" for global attributes.
+    pub element: String,
+
+    /// The data-x of the attribute's definition for that element.
+    pub key: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct Link {
+    /// The term linked to, i.e., the data-x or text of the link.
+    pub term: String,
+
+    /// Where the term is defined: the fragment of its <dfn> in the built spec,
+    /// or the URL of an external definition. None if there is no <dfn>, or if
+    /// its ID is only given to it later, by Wattsi.
+    pub href: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Processor {
+    /// The rows of the Attributes table.
+    rows: Vec<Handle>,
+
+    /// Map from each term defined by a <dfn> to where it links to.
+    dfn_hrefs: HashMap<String, String>,
+}
+
+fn data_x() -> QualName {
+    QualName::new(None, ns!(), LocalName::from("data-x"))
+}
+
+/// Returns what the node refers to in cross-references: its data-x, or
+/// otherwise its text, in lowercase.
+fn term(node: &Handle) -> String {
+    let term = node
+        .get_attribute(&data_x())
+        .unwrap_or_else(|| node.text_content());
+    normalize(&term).to_lowercase()
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Returns the text of the nodes, with whitespace collapsed.
+fn text_of<'b>(nodes: impl Iterator<Item = &'b Handle>) -> String {
+    let text: String = nodes.map(|n| n.text_content().to_string()).collect();
+    normalize(&text)
+}
+
+impl Processor {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        const ID: QualName = QualName {
+            prefix: None,
+            ns: ns!(),
+            local: local_name!("id"),
+        };
+        if node.is_html_element(&local_name!("dfn")) {
+            let term = term(node);
+            let data_x_href = QualName::new(None, ns!(), LocalName::from("data-x-href"));
+            let href = match (node.get_attribute(&data_x_href), node.get_attribute(&ID)) {
+                (Some(url), _) => Some(url.to_string()),
+                (None, Some(id)) => Some(format!("#{id}")),
+                (None, None) => None,
+            };
+            if let Some(href) = href {
+                self.dfn_hrefs.entry(term).or_insert(href);
+            }
+        }
+        if node.is_html_element(&local_name!("tr"))
+            && node.parent_node().is_some_and(|tbody| {
+                tbody.is_html_element(&local_name!("tbody"))
+                    && tbody
+                        .parent_node()
+                        .is_some_and(|table| table.has_id("attributes-1"))
+            })
+        {
+            self.rows.push(node.clone());
+        }
+    }
+
+    /// Returns the metadata for each row of the table, in document order.
+    pub fn extract(self) -> Vec<AttributeMetadata> {
+        let mut result = Vec::new();
+        for row in self.rows.iter() {
+            // Rows have the same structure as described in annotate_attributes:
+            // name, elements, description and valid values.
+            let children = row.children.borrow();
+            let Some(th) = children
+                .iter()
+                .find(|c| c.is_html_element(&local_name!("th")))
+            else {
+                continue;
+            };
+            let tds: Vec<&Handle> = children
+                .iter()
+                .filter(|c| c.is_html_element(&local_name!("td")))
+                .collect();
+            let [elements_td, description_td, value_td, ..] = tds[..] else {
+                continue;
+            };
+
+            let applies_to = elements_td
+                .children
+                .borrow()
+                .iter()
+                .filter(|c| c.is_element())
+                .map(|c| AppliesTo {
+                    element: normalize(&c.text_content()),
+                    key: c
+                        .get_attribute(&data_x())
+                        .filter(|k| !k.is_empty())
+                        .map(|k| k.to_string()),
+                })
+                .collect();
+
+            let description_children = description_td.children.borrow();
+            let mut variant_description = None;
+            for node in description_children.iter() {
+                if let NodeData::Comment { ref contents } = node.data
+                    && let Some(variant) = contents.trim().strip_prefix("or:")
+                {
+                    variant_description = Some(normalize(variant));
+                }
+            }
+
+            let mut value_links = Vec::new();
+            dom_utils::scan_dom(value_td, &mut |n| {
+                let is_link = n.is_html_element(&local_name!("span"))
+                    || n.is_html_element(&local_name!("a"))
+                    || (n.is_html_element(&local_name!("code"))
+                        && n.get_attribute(&data_x()).is_some_and(|d| !d.is_empty()));
+                if is_link && n.get_attribute(&data_x()).is_none_or(|d| !d.is_empty()) {
+                    let term = term(n);
+                    let href = self.dfn_hrefs.get(&term).cloned();
+                    value_links.push(Link { term, href });
+                }
+            });
+
+            result.push(AttributeMetadata {
+                name: normalize(&th.text_content()),
+                applies_to,
+                description: text_of(description_children.iter()),
+                variant_description,
+                value: normalize(&value_td.text_content()),
+                value_links,
+            });
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_document_async;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_extract() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<p>A <dfn id="valid-url">valid URL</dfn> is...
+<p>A <dfn>valid non-negative integer</dfn> is...
+<p>The <dfn data-x="attr-hidden-until-found-state">hidden until found</dfn> state...
+<p>The <dfn data-x-href="https://infra.spec.whatwg.org/#empty-string">empty string</dfn>...
+<table id="attributes-1"><tbody>
+<tr><th><code data-x>href</code>
+    <td><code data-x="attr-a-href">a</code>; <code data-x="attr-area-href">area</code>
+    <td>Address of the <span>hyperlink</span>
+    <td><span>Valid URL</span> potentially surrounded by spaces
+<tr><th><code data-x>hidden</code>
+    <td><span data-x="attr-hidden">HTML elements</span>
+    <td>Whether the element is relevant
+    <td>"<code data-x="attr-hidden-until-found-state">until-found</code>"; "<code data-x="">hidden</code>"; the <span>empty string</span>
+<tr><th><code data-x>width</code>
+    <td><code data-x="attr-dim-width">img</code>
+    <td>Horizontal dimension <!-- or: Width of the thing -->
+    <td><span>Valid non-negative integer</span>
+</tbody></table>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let attributes = proc.extract();
+        assert_eq!(
+            attributes,
+            vec![
+                AttributeMetadata {
+                    name: "href".to_owned(),
+                    applies_to: vec![
+                        AppliesTo {
+                            element: "a".to_owned(),
+                            key: Some("attr-a-href".to_owned()),
+                        },
+                        AppliesTo {
+                            element: "area".to_owned(),
+                            key: Some("attr-area-href".to_owned()),
+                        },
+                    ],
+                    description: "Address of the hyperlink".to_owned(),
+                    variant_description: None,
+                    value: "Valid URL potentially surrounded by spaces".to_owned(),
+                    value_links: vec![Link {
+                        term: "valid url".to_owned(),
+                        href: Some("#valid-url".to_owned()),
+                    }],
+                },
+                AttributeMetadata {
+                    name: "hidden".to_owned(),
+                    applies_to: vec![AppliesTo {
+                        element: "HTML elements".to_owned(),
+                        key: Some("attr-hidden".to_owned()),
+                    }],
+                    description: "Whether the element is relevant".to_owned(),
+                    variant_description: None,
+                    value: "\"until-found\"; \"hidden\"; the empty string".to_owned(),
+                    value_links: vec![
+                        Link {
+                            term: "attr-hidden-until-found-state".to_owned(),
+                            href: None,
+                        },
+                        Link {
+                            term: "empty string".to_owned(),
+                            href: Some("https://infra.spec.whatwg.org/#empty-string".to_owned()),
+                        },
+                    ],
+                },
+                AttributeMetadata {
+                    name: "width".to_owned(),
+                    applies_to: vec![AppliesTo {
+                        element: "img".to_owned(),
+                        key: Some("attr-dim-width".to_owned()),
+                    }],
+                    description: "Horizontal dimension".to_owned(),
+                    variant_description: Some("Width of the thing".to_owned()),
+                    value: "Valid non-negative integer".to_owned(),
+                    value_links: vec![Link {
+                        term: "valid non-negative integer".to_owned(),
+                        href: None,
+                    }],
+                },
+            ]
+        );
+        Ok(())
+    }
+}
@@ -2,44 +2,268 @@
 //! These can either be comment nodes (in which case the resulting fragment will
 //! be inserted), or the complete value of an element's attribute (in which case
 //! the text will become the attribute value).
+//!
+//! Also replaces `EXAMPLE path` text inside `<pre>` and `<pre><code>` with the
+//! contents of a demo file. The path may be followed by `#L10-L25` to select a
+//! range of lines, or by `#name` to select the lines between `region: name` and
+//! `endregion` marker comments. Selected lines are dedented. Demos with a
+//! `.html` extension must parse without errors, unless the `<pre>` or `<code>`
+//! has `class="bad"`.
 
 use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock};
 
-use html5ever::tendril::{self, SendTendril};
 use html5ever::{Attribute, LocalName, QualName, local_name};
 use markup5ever_rcdom::{Handle, NodeData};
-use tokio::fs::File;
+use regex::Regex;
 use tokio::task::JoinHandle;
 
 use crate::dom_utils::NodeHandleExt;
-use crate::io_utils::{async_error, is_safe_path, read_to_str_tendril};
 use crate::parser;
-
-type SendStrTendril = SendTendril<tendril::fmt::UTF8>;
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, line_prefix};
+use crate::source_fs::{SourceFs, spawn_read, spawn_read_to_string};
 
 enum Edit {
-    ReplaceHTML(Handle, JoinHandle<io::Result<File>>),
-    ReplaceAttr(Handle, QualName, JoinHandle<io::Result<SendStrTendril>>),
-    ReplaceText(Handle, JoinHandle<io::Result<SendStrTendril>>),
+    ReplaceHTML(Handle, JoinHandle<io::Result<Vec<u8>>>),
+    ReplaceAttr(Handle, QualName, JoinHandle<io::Result<String>>),
+    ReplaceText {
+        node: Handle,
+        contents: JoinHandle<io::Result<String>>,
+        fragment: ExampleFragment,
+        /// The path of an HTML demo, which must parse without errors.
+        validate: Option<PathBuf>,
+    },
 }
 
-pub struct Processor {
-    /// Path to look for boilerplate files.
+/// Matches an EXAMPLE fragment which selects lines, like `L10-L25`.
+static LINES_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^L(\d+)(?:-L(\d+))?$").unwrap());
+
+/// Matches a `region: name` marker comment, capturing the name.
+static REGION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?://|/\*|<!--)\s*region:\s*([\w.-]+)").unwrap());
+
+/// Matches an `endregion` marker comment.
+static ENDREGION_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^\s*(?://|/\*|<!--)\s*endregion\b").unwrap());
+
+/// The part of an example file which an EXAMPLE directive asks for.
+#[derive(Debug, PartialEq, Eq)]
+enum ExampleFragment {
+    /// The entire file.
+    Whole,
+
+    /// An inclusive, 1-based range of lines, written `#L10-L25` (or `#L10`).
+    Lines(usize, usize),
+
+    /// The lines between `region: name` and the matching `endregion`.
+    Region(String),
+}
+
+impl ExampleFragment {
+    /// Splits an EXAMPLE target like `path#L10-L25` into the path and the
+    /// fragment it selects.
+    fn parse(target: &str) -> (&str, ExampleFragment) {
+        let (path, fragment) = match target.split_once('#') {
+            Some((path, fragment)) => (path, fragment),
+            None => return (target, ExampleFragment::Whole),
+        };
+        let fragment = match LINES_RE.captures(fragment) {
+            Some(c) => {
+                let start = c[1].parse().unwrap_or(0);
+                let end = c.get(2).map_or(start, |m| m.as_str().parse().unwrap_or(0));
+                ExampleFragment::Lines(start, end)
+            }
+            None => ExampleFragment::Region(fragment.to_owned()),
+        };
+        (path, fragment)
+    }
+
+    /// Returns the selected part of the example text. Region marker lines are
+    /// never included, and partial selections are dedented.
+    fn select(&self, text: &str) -> io::Result<String> {
+        let is_marker = |line: &str| REGION_RE.is_match(line) || ENDREGION_RE.is_match(line);
+
+        let lines: Vec<&str> = match self {
+            ExampleFragment::Whole => {
+                if !text.lines().any(is_marker) {
+                    return Ok(text.to_owned());
+                }
+                let mut result: String = text
+                    .lines()
+                    .filter(|l| !is_marker(l))
+                    .flat_map(|l| [l, "\n"])
+                    .collect();
+                if !text.ends_with('\n') {
+                    result.pop();
+                }
+                return Ok(result);
+            }
+            ExampleFragment::Lines(start, end) => {
+                let all: Vec<&str> = text.lines().collect();
+                if *start == 0 || start > end || *end > all.len() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "lines {start}-{end} are out of range (the file has {} lines)",
+                            all.len()
+                        ),
+                    ));
+                }
+                all[start - 1..*end]
+                    .iter()
+                    .copied()
+                    .filter(|l| !is_marker(l))
+                    .collect()
+            }
+            ExampleFragment::Region(name) => {
+                let mut iter = text
+                    .lines()
+                    .skip_while(|l| REGION_RE.captures(l).is_none_or(|c| &c[1] != name.as_str()));
+                if iter.next().is_none() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("region {name:?} not found"),
+                    ));
+                }
+                // Nested regions must be closed before this one is.
+                let mut depth = 1;
+                let mut selected = Vec::new();
+                for line in iter {
+                    if REGION_RE.is_match(line) {
+                        depth += 1;
+                    } else if ENDREGION_RE.is_match(line) {
+                        depth -= 1;
+                        if depth == 0 {
+                            break;
+                        }
+                    } else {
+                        selected.push(line);
+                    }
+                }
+                if depth != 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("region {name:?} has no matching endregion"),
+                    ));
+                }
+                selected
+            }
+        };
+        Ok(dedent(&lines))
+    }
+}
+
+/// Checks that an HTML demo parses as a document without any errors.
+async fn validate_demo(path: &Path, text: &str) -> io::Result<()> {
+    let dom = parser::parse_document_unchecked_async(text.as_bytes()).await?;
+    let errors = dom.parse_error_messages();
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!(
+            "Parse errors encountered in example {}:\n\n{}",
+            path.display(),
+            errors.join("\n")
+        ),
+    ))
+}
+
+/// Removes the longest leading whitespace which the non-blank lines all have
+/// in common, and joins them back together with a trailing newline. A tab and
+/// a space are different indentation, so neither is removed where they differ.
+fn dedent(lines: &[&str]) -> String {
+    fn indent_of(line: &str) -> &str {
+        &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+    }
+    let is_blank = |l: &str| indent_of(l).len() == l.len();
+    let indent = lines
+        .iter()
+        .filter(|l| !is_blank(l))
+        .map(|l| indent_of(l))
+        .reduce(|common, indent| {
+            let len = common
+                .bytes()
+                .zip(indent.bytes())
+                .take_while(|(a, b)| a == b)
+                .count();
+            &common[..len]
+        })
+        .unwrap_or("");
+    let mut result = String::new();
+    for line in lines {
+        if !is_blank(line) {
+            result.push_str(&line[indent.len()..]);
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// Where a replacement was requested, and where its file was looked for, so
+/// that failures can be reported usefully.
+struct Origin {
+    /// The line of the source on which the directive appears.
+    line: Option<u64>,
+
+    /// The directive, as written.
+    directive: String,
+
+    /// Where the file was looked for. This is only described if something goes
+    /// wrong, since describing it can take I/O of its own.
+    fs: Arc<dyn SourceFs>,
+
+    /// The path of the file, relative to the root of `fs`.
     path: PathBuf,
+}
 
-    /// Path to look for example files.
-    example_path: PathBuf,
+impl Origin {
+    fn new(line: Option<u64>, directive: String, fs: &Arc<dyn SourceFs>, path: &Path) -> Self {
+        Self {
+            line,
+            directive,
+            fs: fs.clone(),
+            path: path.to_owned(),
+        }
+    }
+
+    fn describe_error(&self, error: &io::Error) -> String {
+        format!(
+            "{}{}: {error} (resolved to {}, searching {})",
+            line_prefix(self.line),
+            self.directive,
+            self.fs.describe_path(&self.path),
+            self.fs.describe_root()
+        )
+    }
+}
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// Where to look for boilerplate files.
+    boilerplate_fs: Arc<dyn SourceFs>,
+
+    /// Where to look for example files.
+    example_fs: Arc<dyn SourceFs>,
 
     /// Changes to be made in the apply step.
-    edits: Vec<Edit>,
+    edits: Vec<(Origin, Edit)>,
 }
 
-impl Processor {
-    pub fn new(path: impl Into<PathBuf>, example_path: impl Into<PathBuf>) -> Self {
+impl<'a> Processor<'a> {
+    pub fn new(
+        parsed: &'a RcDomWithLineNumbers,
+        boilerplate_fs: Arc<dyn SourceFs>,
+        example_fs: Arc<dyn SourceFs>,
+    ) -> Self {
         Self {
-            path: path.into(),
-            example_path: example_path.into(),
+            parsed,
+            boilerplate_fs,
+            example_fs,
             edits: vec![],
         }
     }
@@ -54,15 +278,15 @@ impl Processor {
             // demand.
             NodeData::Comment { contents } if contents.starts_with("BOILERPLATE ") => {
                 let path = Path::new(contents[12..].trim());
-                let file = if is_safe_path(path) {
-                    tokio::spawn(File::open(self.path.join(path)))
-                } else {
-                    async_error(io::Error::new(
-                        io::ErrorKind::PermissionDenied,
-                        "cannot traverse to a parent directory in {path}",
-                    ))
-                };
-                self.edits.push(Edit::ReplaceHTML(node.clone(), file));
+                let origin = Origin::new(
+                    self.parsed.line_number_for(node),
+                    format!("<!--{contents}-->"),
+                    &self.boilerplate_fs,
+                    path,
+                );
+                let file = spawn_read(&self.boilerplate_fs, path);
+                self.edits
+                    .push((origin, Edit::ReplaceHTML(node.clone(), file)));
             }
             // Pseudo-comments can also appear in element attributes. These are
             // not parsed as HTML, so we simply want to read them into memory so
@@ -71,18 +295,16 @@ impl Processor {
                 for Attribute { name, value } in attrs.borrow().iter() {
                     if value.starts_with("<!--BOILERPLATE ") && value.ends_with("-->") {
                         let path = Path::new(value[16..value.len() - 3].trim());
-                        let file_contents = if is_safe_path(path) {
-                            read_to_str_tendril(self.path.join(path))
-                        } else {
-                            async_error(io::Error::new(
-                                io::ErrorKind::PermissionDenied,
-                                "cannot traverse to a parent directory in {path}",
-                            ))
-                        };
-                        self.edits.push(Edit::ReplaceAttr(
-                            node.clone(),
-                            name.clone(),
-                            file_contents,
+                        let origin = Origin::new(
+                            self.parsed.line_number_for(node),
+                            format!("{}=\"{value}\"", name.local),
+                            &self.boilerplate_fs,
+                            path,
+                        );
+                        let file_contents = spawn_read_to_string(&self.boilerplate_fs, path);
+                        self.edits.push((
+                            origin,
+                            Edit::ReplaceAttr(node.clone(), name.clone(), file_contents),
                         ));
                     }
                 }
@@ -98,80 +320,132 @@ impl Processor {
                 }
                 const PRE: LocalName = local_name!("pre");
                 const CODE: LocalName = local_name!("code");
-                let has_suitable_parent = node.parent_node().is_some_and(|p| {
-                    p.is_html_element(&PRE)
-                        || (p.is_html_element(&CODE)
-                            && p.parent_node().is_some_and(|p2| p2.is_html_element(&PRE)))
-                });
-                if has_suitable_parent {
-                    let path = Path::new(text[8..].trim());
-                    let file_contents = if is_safe_path(path) {
-                        read_to_str_tendril(self.example_path.join(path))
+                let parent = node.parent_node();
+                let pre = parent.iter().find_map(|p| {
+                    if p.is_html_element(&PRE) {
+                        Some(p.clone())
+                    } else if p.is_html_element(&CODE) {
+                        p.parent_node().filter(|p2| p2.is_html_element(&PRE))
                     } else {
-                        async_error(io::Error::new(
-                            io::ErrorKind::PermissionDenied,
-                            "cannot traverse to a parent directory in {path}",
-                        ))
-                    };
-                    self.edits
-                        .push(Edit::ReplaceText(node.clone(), file_contents))
+                        None
+                    }
+                });
+                if let Some(pre) = pre {
+                    let (path, fragment) = ExampleFragment::parse(text[8..].trim());
+                    let path = Path::new(path);
+                    // Text nodes aren't tracked, but the element they're in is.
+                    let origin = Origin::new(
+                        parent.as_ref().and_then(|p| self.parsed.line_number_for(p)),
+                        text.to_owned(),
+                        &self.example_fs,
+                        path,
+                    );
+                    let is_bad_example =
+                        pre.has_class("bad") || parent.is_some_and(|p| p.has_class("bad"));
+                    let validate = (!is_bad_example
+                        && path.extension().is_some_and(|e| e == "html"))
+                    .then(|| path.to_owned());
+                    let file_contents = spawn_read_to_string(&self.example_fs, path);
+                    self.edits.push((
+                        origin,
+                        Edit::ReplaceText {
+                            node: node.clone(),
+                            contents: file_contents,
+                            fragment,
+                            validate,
+                        },
+                    ))
                 }
             }
             _ => (),
         }
     }
 
-    /// Applies the required replacements, in order.
+    /// Applies the required replacements, in order. Failures are collected so
+    /// that they can all be reported at once.
     pub async fn apply(self) -> io::Result<()> {
-        for edit in self.edits {
-            match edit {
-                // When parsing HTML, we need the context it's in so that the
-                // context-sensitive parsing behavior works correctly.
-                Edit::ReplaceHTML(node, replacement) => {
-                    let context = match node.parent_node() {
-                        Some(n) => n,
-                        _ => continue,
-                    };
-                    let file: File = replacement.await??;
-                    let new_children = parser::parse_fragment_async(file, &context).await?;
-                    node.replace_with(new_children);
-                }
-                Edit::ReplaceAttr(element, ref attr, replacement) => {
-                    element.set_attribute(attr, replacement.await??.into());
-                }
-                Edit::ReplaceText(element, replacement) => match element.data {
-                    NodeData::Text { ref contents } => {
-                        contents.replace(replacement.await??.into());
-                    }
-                    _ => panic!("not text"),
-                },
+        let mut errors = Vec::new();
+        for (origin, edit) in self.edits {
+            if let Err(e) = apply_edit(edit).await {
+                errors.push((origin, e));
             }
         }
-        Ok(())
+        let kind = match errors.first() {
+            None => return Ok(()),
+            Some((_, e)) => e.kind(),
+        };
+        let mut messages: Vec<String> = errors
+            .iter()
+            .map(|(origin, e)| origin.describe_error(e))
+            .collect();
+        let message = if messages.len() == 1 {
+            messages.pop().unwrap()
+        } else {
+            format!(
+                "{} boilerplate replacements failed:\n{}",
+                messages.len(),
+                messages.join("\n")
+            )
+        };
+        Err(io::Error::new(kind, message))
     }
 }
 
+async fn apply_edit(edit: Edit) -> io::Result<()> {
+    match edit {
+        // When parsing HTML, we need the context it's in so that the
+        // context-sensitive parsing behavior works correctly.
+        Edit::ReplaceHTML(node, replacement) => {
+            let file = replacement.await??;
+            let context = match node.parent_node() {
+                Some(n) => n,
+                _ => return Ok(()),
+            };
+            let new_children = parser::parse_fragment_async(&file[..], &context).await?;
+            node.replace_with(new_children);
+        }
+        Edit::ReplaceAttr(element, ref attr, replacement) => {
+            element.set_attribute(attr, replacement.await??.into());
+        }
+        Edit::ReplaceText {
+            node,
+            contents: replacement,
+            fragment,
+            validate,
+        } => match node.data {
+            NodeData::Text { ref contents } => {
+                let text = replacement.await??;
+                if let Some(path) = validate {
+                    validate_demo(&path, &text).await?;
+                }
+                contents.replace(fragment.select(&text)?.into());
+            }
+            _ => panic!("not text"),
+        },
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::dom_utils;
     use crate::parser::{parse_document_async, tests::serialize_for_test};
-    use tempfile::TempDir;
+    use crate::source_fs::{DiskFs, MemoryFs};
+
+    fn empty_fs() -> Arc<dyn SourceFs> {
+        Arc::new(MemoryFs::new())
+    }
 
     #[tokio::test]
     async fn test_replace_boilerplate_comment() -> io::Result<()> {
-        let boilerplate_dir = TempDir::new()?;
-        tokio::fs::write(
-            boilerplate_dir.path().join("languages"),
-            "<tr><td>en<td>English",
-        )
-        .await?;
+        let boilerplate_fs = MemoryFs::new().with_file("languages", "<tr><td>en<td>English");
         let parsed = parse_document_async(
             "<!DOCTYPE html><table><!--BOILERPLATE languages--></table>".as_bytes(),
         )
         .await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new(boilerplate_dir.path(), Path::new("."));
+        let mut proc = Processor::new(&parsed, Arc::new(boilerplate_fs), empty_fs());
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -183,18 +457,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_replace_boilerplate_attribute() -> io::Result<()> {
-        let boilerplate_dir = TempDir::new()?;
-        tokio::fs::write(
-            boilerplate_dir.path().join("data.url"),
-            "data:text/html,Hello, world!",
-        )
-        .await?;
+        let boilerplate_fs = MemoryFs::new().with_file("data.url", "data:text/html,Hello, world!");
         let parsed = parse_document_async(
             "<!DOCTYPE html><a href=\"<!--BOILERPLATE data.url-->\">hello</a>".as_bytes(),
         )
         .await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new(boilerplate_dir.path(), Path::new("."));
+        let mut proc = Processor::new(&parsed, Arc::new(boilerplate_fs), empty_fs());
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -206,15 +475,15 @@ mod tests {
 
     #[tokio::test]
     async fn test_replace_example() -> io::Result<()> {
-        let example_dir = TempDir::new()?;
-        tokio::fs::write(example_dir.path().join("ex1"), "first").await?;
-        tokio::fs::write(example_dir.path().join("ex2"), "second").await?;
-        tokio::fs::write(example_dir.path().join("ignored"), "bad").await?;
+        let example_fs = MemoryFs::new()
+            .with_file("ex1", "first")
+            .with_file("ex2", "second")
+            .with_file("ignored", "bad");
         let parsed =
             parse_document_async("<!DOCTYPE html><pre>EXAMPLE ex1</pre><pre><code class=html>\nEXAMPLE ex2  </code></pre><p>EXAMPLE ignored</p>".as_bytes())
                 .await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new(Path::new("."), example_dir.path());
+        let mut proc = Processor::new(&parsed, empty_fs(), Arc::new(example_fs));
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply().await?;
         assert_eq!(
@@ -224,6 +493,176 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_replace_example_fragments() -> io::Result<()> {
+        let example_fs = MemoryFs::new().with_file(
+            "demo.js",
+            "function f() {\n  // region: body\n  let x = 1;\n\n    return x;\n  // endregion\n}\n",
+        );
+        let parsed = parse_document_async(
+            "<!DOCTYPE html><pre>EXAMPLE demo.js#L1-L1</pre><pre><code>EXAMPLE demo.js#body</code></pre><pre>EXAMPLE demo.js</pre>"
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed, empty_fs(), Arc::new(example_fs));
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        proc.apply().await?;
+        assert_eq!(
+            serialize_for_test(&[document]),
+            "<!DOCTYPE html><html><head></head><body><pre>function f() {\n</pre><pre><code>let x = 1;\n\n  return x;\n</code></pre><pre>function f() {\n  let x = 1;\n\n    return x;\n}\n</pre></body></html>"
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_validate_html_examples() -> io::Result<()> {
+        let example_fs: Arc<dyn SourceFs> = Arc::new(
+            MemoryFs::new()
+                .with_file(
+                    "good.html",
+                    "<!DOCTYPE html>\n<title>Good</title>\n<p>Fine.\n",
+                )
+                .with_file(
+                    "broken.html",
+                    "<!DOCTYPE html>\n<title>Broken</title>\n<p><b><i>x</b></i>\n",
+                )
+                .with_file("broken.txt", "<b><i>x</b></i>"),
+        );
+
+        let valid_examples = [
+            "<!DOCTYPE html><pre>EXAMPLE good.html</pre>",
+            "<!DOCTYPE html><pre class=bad>EXAMPLE broken.html</pre>",
+            "<!DOCTYPE html><pre><code class=\"html bad\">EXAMPLE broken.html</code></pre>",
+            "<!DOCTYPE html><pre>EXAMPLE broken.txt</pre>",
+        ];
+        for example in valid_examples {
+            let parsed = parse_document_async(example.as_bytes()).await?;
+            let document = parsed.document().clone();
+            let mut proc = Processor::new(&parsed, empty_fs(), example_fs.clone());
+            dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+            proc.apply().await?;
+        }
+
+        let parsed =
+            parse_document_async("<!DOCTYPE html><pre>EXAMPLE broken.html#L3</pre>".as_bytes())
+                .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed, empty_fs(), example_fs);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let error = proc.apply().await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("broken.html"));
+        assert!(error.to_string().contains("Line 3: "));
+        Ok(())
+    }
+
+    #[test]
+    fn test_example_fragment_selection() {
+        assert_eq!(
+            ExampleFragment::parse("a/b.html#L3-L10"),
+            ("a/b.html", ExampleFragment::Lines(3, 10))
+        );
+        assert_eq!(
+            ExampleFragment::parse("b.html#L7"),
+            ("b.html", ExampleFragment::Lines(7, 7))
+        );
+        assert_eq!(
+            ExampleFragment::parse("b.html#setup"),
+            ("b.html", ExampleFragment::Region("setup".to_owned()))
+        );
+        assert_eq!(
+            ExampleFragment::parse("b.html"),
+            ("b.html", ExampleFragment::Whole)
+        );
+
+        let text = "<ul>\n  <!-- region: outer -->\n  <li>\n    <!-- region: inner -->\n    <b>x</b>\n    <!-- endregion -->\n  </li>\n  <!-- endregion -->\n</ul>";
+        assert_eq!(
+            ExampleFragment::Region("outer".to_owned())
+                .select(text)
+                .unwrap(),
+            "<li>\n  <b>x</b>\n</li>\n"
+        );
+        assert_eq!(
+            ExampleFragment::Region("inner".to_owned())
+                .select(text)
+                .unwrap(),
+            "<b>x</b>\n"
+        );
+        assert_eq!(
+            ExampleFragment::Lines(3, 5).select(text).unwrap(),
+            "<li>\n  <b>x</b>\n"
+        );
+        assert_eq!(
+            ExampleFragment::Whole.select(text).unwrap(),
+            "<ul>\n  <li>\n    <b>x</b>\n  </li>\n</ul>"
+        );
+        assert_eq!(
+            ExampleFragment::Lines(1, 3)
+                .select("\t\tif (x) {\n\t\t  y();\n    }\n")
+                .unwrap(),
+            "\t\tif (x) {\n\t\t  y();\n    }\n"
+        );
+        assert_eq!(
+            ExampleFragment::Lines(1, 2)
+                .select("\t  a\n\t b\n")
+                .unwrap(),
+            " a\nb\n"
+        );
+        assert!(ExampleFragment::Lines(8, 20).select(text).is_err());
+        assert!(ExampleFragment::Lines(0, 1).select(text).is_err());
+        assert!(
+            ExampleFragment::Region("missing".to_owned())
+                .select(text)
+                .is_err()
+        );
+        assert!(
+            ExampleFragment::Region("open".to_owned())
+                .select("// region: open\nx\n")
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_located_and_collected() -> io::Result<()> {
+        let boilerplate_fs = MemoryFs::new().with_file("present", "<p>here</p>");
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html><body>
+<!--BOILERPLATE missing-1-->
+<!--BOILERPLATE present-->
+<a href="<!--BOILERPLATE missing-2-->">link</a>
+<pre><code>
+EXAMPLE missing-3.js
+</code></pre>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed, Arc::new(boilerplate_fs), empty_fs());
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let error = proc.apply().await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        let message = error.to_string();
+        assert!(
+            message.starts_with("3 boilerplate replacements failed"),
+            "{message}"
+        );
+        assert!(
+            message.contains("Line 2: <!--BOILERPLATE missing-1-->: missing-1 not found in memory (resolved to missing-1 in memory, searching files in memory)"),
+            "{message}"
+        );
+        assert!(
+            message.contains("Line 4: href=\"<!--BOILERPLATE missing-2-->\": "),
+            "{message}"
+        );
+        assert!(
+            message.contains("Line 5: EXAMPLE missing-3.js: "),
+            "{message}"
+        );
+        assert!(!message.contains("present"), "{message}");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_errors_unsafe_paths() -> io::Result<()> {
         let bad_path_examples = [
@@ -234,7 +673,11 @@ mod tests {
         for example in bad_path_examples {
             let parsed = parse_document_async(example.as_bytes()).await?;
             let document = parsed.document().clone();
-            let mut proc = Processor::new(Path::new("."), Path::new("."));
+            let mut proc = Processor::new(
+                &parsed,
+                Arc::new(DiskFs::new(Path::new("."))),
+                Arc::new(DiskFs::new(Path::new("."))),
+            );
             dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
             let result = proc.apply().await;
             assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::PermissionDenied));
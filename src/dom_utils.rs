@@ -194,6 +194,48 @@ pub fn heading_level(node: &Handle) -> Option<u8> {
     }
 }
 
+/// Associates each <dl class="element"> with the elements whose <dfn element>
+/// precede it, e.g., all six of <h1> through <h6> share one.
+#[derive(Default)]
+pub struct ElementDlTracker {
+    /// Element dfns seen since the last heading or <dl class="element">.
+    pending_dfns: Vec<StrTendril>,
+}
+
+impl ElementDlTracker {
+    /// Should be called for each node in document order. If `node` is a
+    /// <dl class="element"> which follows one or more <dfn element>, returns
+    /// the names of the elements it describes.
+    pub fn visit(&mut self, node: &Handle) -> Option<Vec<StrTendril>> {
+        if heading_level(node).is_some() {
+            self.pending_dfns.clear();
+        } else if node.is_html_element(&local_name!("dfn"))
+            && node.has_attribute(&QualName::new(None, ns!(), LocalName::from("element")))
+        {
+            self.pending_dfns.push(node.text_content());
+        } else if node.is_html_element(&local_name!("dl"))
+            && node.has_class("element")
+            && !self.pending_dfns.is_empty()
+        {
+            return Some(std::mem::take(&mut self.pending_dfns));
+        }
+        None
+    }
+}
+
+/// Returns the text with its first letter in ASCII uppercase.
+pub fn capitalize(text: &str) -> StrTendril {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(c) => {
+            let mut capitalized = StrTendril::from_char(c.to_ascii_uppercase());
+            capitalized.push_slice(chars.as_str());
+            capitalized
+        }
+        None => StrTendril::new(),
+    }
+}
+
 impl NodeHandleExt for Handle {
     fn parent_node(&self) -> Option<Handle> {
         let weak_parent = self.parent.take()?;
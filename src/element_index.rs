@@ -0,0 +1,307 @@
+//! Generates the "List of elements" index table from the <dl class="element">
+//! and "represents" paragraph of each element, so that it cannot disagree with
+//! them. The table replaces an <!--INSERT ELEMENT INDEX--> comment; if there is
+//! none, nothing is generated.
+
+use std::collections::HashMap;
+use std::io;
+use std::rc::Rc;
+
+use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::{Handle, NodeData};
+
+use crate::dom_utils::{self, NodeHandleExt, capitalize};
+use crate::interface_index;
+use crate::represents;
+
+/// The comment which marks where the index belongs.
+const MARKER: &str = "INSERT ELEMENT INDEX";
+
+/// The columns after "Element" and "Description", and the keys of the
+/// corresponding <dt> in the element definitions.
+const COLUMNS: [(&str, &str); 5] = [
+    ("Categories", "concept-element-categories"),
+    ("Parents", "concept-element-contexts"),
+    ("Children", "concept-element-content-model"),
+    ("Attributes", "concept-element-attributes"),
+    ("Interface", "concept-element-dom"),
+];
+
+#[derive(Default)]
+pub struct Processor {
+    /// Finds the <dl class="element"> for each element.
+    element_dls: dom_utils::ElementDlTracker,
+
+    /// The <dl class="element"> blocks, with the elements they describe.
+    dls: Vec<(Vec<StrTendril>, Handle)>,
+
+    /// Map from tag name to the <span> which contains the text "represents".
+    represents: HashMap<StrTendril, Handle>,
+
+    /// The <!--INSERT ELEMENT INDEX--> comments.
+    marker_nodes: Vec<Handle>,
+}
+
+/// Deep clones the node, without any IDs, so as not to duplicate them.
+fn clone_without_ids(node: &Handle) -> Handle {
+    const ID: QualName = QualName {
+        prefix: None,
+        ns: ns!(),
+        local: local_name!("id"),
+    };
+    let clone = node.deep_clone();
+    dom_utils::scan_dom(&clone, &mut |n| {
+        if n.is_element() {
+            n.remove_attribute(&ID);
+        }
+    });
+    clone
+}
+
+fn cell(name: LocalName, children: Vec<Handle>) -> Handle {
+    Handle::create_element(name)
+        .children(children.into_iter())
+        .build()
+}
+
+impl Processor {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if let Some(elements) = self.element_dls.visit(node) {
+            self.dls.push((elements, node.clone()));
+        }
+        match node.data {
+            NodeData::Text { ref contents } if contents.borrow().as_ref() == "represents" => {
+                if let Some((tag, span)) = represents::find_tag_name(node) {
+                    self.represents.entry(tag).or_insert(span);
+                }
+            }
+            NodeData::Comment { ref contents } if contents.trim() == MARKER => {
+                self.marker_nodes.push(node.clone());
+            }
+            _ => (),
+        }
+    }
+
+    /// Returns a copy of the rest of the "represents" sentence for the element.
+    fn description(&self, tag: &StrTendril) -> Vec<Handle> {
+        let Some(span) = self.represents.get(tag) else {
+            return Vec::new();
+        };
+        let Some(p) = span.parent_node() else {
+            return Vec::new();
+        };
+        p.children
+            .borrow()
+            .iter()
+            .skip_while(|s| !Rc::ptr_eq(s, span))
+            .skip(1)
+            .enumerate()
+            .map(|(index, sibling)| {
+                let clone = clone_without_ids(sibling);
+                if let (0, NodeData::Text { contents }) = (index, &clone.data) {
+                    contents.replace_with(|text| capitalize(text.trim_start()));
+                }
+                clone
+            })
+            .collect()
+    }
+
+    /// Returns the contents of the <dd>s for the given <dt> key, separated by
+    /// line breaks.
+    fn definition_cell(dl: &Handle, key: &str) -> Vec<Handle> {
+        let data_x = QualName::new(None, ns!(), LocalName::from("data-x"));
+        let Some(dds) = dl
+            .children
+            .borrow()
+            .iter()
+            .find(|c| {
+                c.is_html_element(&local_name!("dt"))
+                    && c.any_child(|s| s.attribute_is(&data_x, key))
+            })
+            .map(dom_utils::dt_descriptions)
+        else {
+            return Vec::new();
+        };
+
+        let mut contents = Vec::new();
+        for (i, dd) in dds.iter().enumerate() {
+            if i > 0 {
+                contents.push(Handle::create_element(local_name!("br")).build());
+            }
+            match key {
+                // Just the interface name, rather than the whole IDL block.
+                "concept-element-dom" => {
+                    let name = match interface_index::defined_interface(dd) {
+                        Some(name) => Some(StrTendril::from(name)),
+                        None => dd
+                            .children
+                            .borrow()
+                            .iter()
+                            .find(|c| c.is_html_element(&local_name!("code")))
+                            .map(|c| c.text_content()),
+                    };
+                    if let Some(name) = name {
+                        contents.push(
+                            Handle::create_element(local_name!("code"))
+                                .text(name)
+                                .build(),
+                        );
+                    }
+                }
+                // Just the attributes, rather than their descriptions.
+                "concept-element-attributes" => contents.extend(
+                    dd.children
+                        .borrow()
+                        .iter()
+                        .filter(|c| c.is_element())
+                        .map(clone_without_ids),
+                ),
+                _ => contents.extend(dd.children.borrow().iter().map(clone_without_ids)),
+            }
+        }
+        contents
+    }
+
+    pub fn apply(self) -> io::Result<()> {
+        if self.marker_nodes.len() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "<!--{MARKER}--> found {} times, expected at most one.",
+                    self.marker_nodes.len()
+                ),
+            ));
+        }
+        let Some(marker) = self.marker_nodes.first() else {
+            return Ok(());
+        };
+
+        let mut header = Handle::create_element(local_name!("tr"))
+            .child(
+                Handle::create_element(local_name!("th"))
+                    .text("Element")
+                    .build(),
+            )
+            .child(
+                Handle::create_element(local_name!("th"))
+                    .text("Description")
+                    .build(),
+            );
+        for (column, _) in COLUMNS {
+            header = header.child(
+                Handle::create_element(local_name!("th"))
+                    .text(column)
+                    .build(),
+            );
+        }
+
+        // Like the rest of the index, the rows are in alphabetical order.
+        let mut dls: Vec<&(Vec<StrTendril>, Handle)> = self.dls.iter().collect();
+        dls.sort_by(|a, b| a.0.first().cmp(&b.0.first()));
+        let mut tbody = Handle::create_element(local_name!("tbody"));
+        for (elements, dl) in dls {
+            let mut names = Vec::new();
+            for (i, element) in elements.iter().enumerate() {
+                if i > 0 {
+                    names.push(Handle::create_text_node(", "));
+                }
+                names.push(
+                    Handle::create_element(local_name!("code"))
+                        .text(element.clone())
+                        .build(),
+                );
+            }
+            let mut tr = Handle::create_element(local_name!("tr"))
+                .child(cell(local_name!("th"), names))
+                .child(cell(local_name!("td"), self.description(&elements[0])));
+            for (_, key) in COLUMNS {
+                tr = tr.child(cell(local_name!("td"), Self::definition_cell(dl, key)));
+            }
+            tbody = tbody.child(tr.build());
+        }
+
+        let table = Handle::create_element(local_name!("table"))
+            .child(
+                Handle::create_element(local_name!("caption"))
+                    .text("List of elements")
+                    .build(),
+            )
+            .child(
+                Handle::create_element(local_name!("thead"))
+                    .child(header.build())
+                    .build(),
+            )
+            .child(tbody.build())
+            .build();
+        marker.replace_with(vec![table]);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_document_async, tests::serialize_for_test};
+
+    #[tokio::test]
+    async fn test_element_index() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<h4>The <dfn element><code>ul</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-categories">Categories</span>:</dt>
+<dd><span>Flow content</span>.</dd>
+<dd>If the element has <code>li</code> children: <span>Palpable content</span>.</dd>
+<dt><span data-x="concept-element-contexts">Contexts in which this element can be used</span>:</dt>
+<dd>Where <span>flow content</span> is expected.</dd>
+<dt><span data-x="concept-element-content-model">Content model</span>:</dt>
+<dd>Zero or more <code>li</code> elements.</dd>
+<dt><span data-x="concept-element-attributes">Content attributes</span>:</dt>
+<dd><span>Global attributes</span></dd>
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd><pre><code class="idl">interface <dfn interface id="htmlulistelement">HTMLUListElement</dfn> : <span>HTMLElement</span> {};</code></pre></dd>
+</dl>
+<p>The <code>ul</code> element <span>represents</span> a <span id="x">list</span>.
+<h4>The <dfn element><code>b</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-attributes">Content attributes</span>:</dt>
+<dd><span>Global attributes</span></dd>
+<dd><code data-x="attr-b-bold">bold</code> — How bold</dd>
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd>Uses <code>HTMLElement</code>.</dd>
+</dl>
+<!--INSERT ELEMENT INDEX-->"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        proc.apply()?;
+        let serialized = serialize_for_test(&[document]);
+        let table = &serialized[serialized.find("<table>").unwrap()..];
+        assert_eq!(
+            table,
+            r#"<table><caption>List of elements</caption><thead><tr><th>Element</th><th>Description</th><th>Categories</th><th>Parents</th><th>Children</th><th>Attributes</th><th>Interface</th></tr></thead><tbody><tr><th><code>b</code></th><td></td><td></td><td></td><td></td><td><span>Global attributes</span><br><code data-x="attr-b-bold">bold</code></td><td><code>HTMLElement</code></td></tr><tr><th><code>ul</code></th><td>A <span>list</span>.
+</td><td><span>Flow content</span>.<br>If the element has <code>li</code> children: <span>Palpable content</span>.</td><td>Where <span>flow content</span> is expected.</td><td>Zero or more <code>li</code> elements.</td><td><span>Global attributes</span></td><td><code>HTMLUListElement</code></td></tr></tbody></table></body></html>"#
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_no_marker() -> io::Result<()> {
+        let source = r#"<!DOCTYPE html><html><head></head><body><h4>The <dfn element=""><code>b</code></dfn> element</h4><dl class="element"></dl></body></html>"#;
+        let parsed = parse_document_async(source.as_bytes()).await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        proc.apply()?;
+        assert_eq!(serialize_for_test(&[document]), source);
+        Ok(())
+    }
+}
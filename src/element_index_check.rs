@@ -0,0 +1,310 @@
+//! Compares what each <dl class="element"> says about its element with the
+//! corresponding row of the "List of elements" table in the index, and reports
+//! where they disagree.
+//!
+//! The two are written quite differently (e.g., "Where phrasing content is
+//! expected." versus "phrasing"), so only the things which can be matched
+//! reliably are compared: links to content categories and mentions of other
+//! elements, attribute definitions, and the DOM interface.
+
+use std::collections::{BTreeSet, HashMap};
+
+use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::Handle;
+
+use crate::dom_utils::{self, NodeHandleExt};
+use crate::interface_index;
+use crate::rcdom_with_line_numbers::RcDomWithLineNumbers;
+
+/// The columns of the index, and the keys of the corresponding <dt> in the
+/// element definitions.
+const COMPARED: [(&str, &str); 5] = [
+    ("Categories", "concept-element-categories"),
+    ("Parents", "concept-element-contexts"),
+    ("Children", "concept-element-content-model"),
+    ("Attributes", "concept-element-attributes"),
+    ("Interface", "concept-element-dom"),
+];
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// Finds the <dl class="element"> for each element.
+    element_dls: dom_utils::ElementDlTracker,
+
+    /// The <dl class="element"> for each element, in document order.
+    dls: Vec<(StrTendril, Handle)>,
+
+    /// The "List of elements" table, if found.
+    index: Option<Handle>,
+}
+
+fn data_x() -> QualName {
+    QualName::new(None, ns!(), LocalName::from("data-x"))
+}
+
+/// Returns what each <span>, <code> and <a> within `node` refers to: its
+/// data-x, or otherwise its text, in lowercase.
+fn referenced_terms(node: &Handle) -> Vec<String> {
+    let mut terms = Vec::new();
+    dom_utils::scan_dom(node, &mut |n| {
+        if n.is_html_element(&local_name!("span"))
+            || n.is_html_element(&local_name!("code"))
+            || n.is_html_element(&local_name!("a"))
+        {
+            let term = n
+                .get_attribute(&data_x())
+                .filter(|d| !d.is_empty())
+                .unwrap_or_else(|| n.text_content());
+            terms.push(
+                term.split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" ")
+                    .to_lowercase(),
+            );
+        }
+    });
+    terms
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            element_dls: Default::default(),
+            dls: Vec::new(),
+            index: None,
+        }
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if let Some(elements) = self.element_dls.visit(node) {
+            self.dls
+                .extend(elements.into_iter().map(|e| (e, node.clone())));
+        }
+        if node.is_html_element(&local_name!("caption"))
+            && node.text_content().trim() == "List of elements"
+            && let Some(table) = node
+                .parent_node()
+                .filter(|t| t.is_html_element(&local_name!("table")))
+        {
+            self.index = Some(table);
+        }
+    }
+
+    fn line_number(&self, node: &Handle) -> String {
+        self.parsed
+            .line_number_for(node)
+            .map(|ln| ln.to_string())
+            .unwrap_or_else(|| "?".to_owned())
+    }
+
+    /// Returns a description of each disagreement between the element
+    /// definitions and the index. Should be called after visiting the
+    /// document.
+    pub fn warnings(&self) -> Vec<String> {
+        let Some(ref index) = self.index else {
+            return Vec::new();
+        };
+        let element_names: BTreeSet<String> = self.dls.iter().map(|(e, _)| e.to_string()).collect();
+
+        // Find the columns from the header, and then the row for each element.
+        let mut columns: HashMap<&str, usize> = HashMap::new();
+        let mut rows: HashMap<String, (Handle, Vec<Handle>)> = HashMap::new();
+        let mut row_order: Vec<String> = Vec::new();
+        dom_utils::scan_dom(index, &mut |tr| {
+            if !tr.is_html_element(&local_name!("tr")) {
+                return;
+            }
+            let cells: Vec<Handle> = tr
+                .children
+                .borrow()
+                .iter()
+                .filter(|c| {
+                    c.is_html_element(&local_name!("th")) || c.is_html_element(&local_name!("td"))
+                })
+                .cloned()
+                .collect();
+            if columns.is_empty() {
+                for (i, cell) in cells.iter().enumerate() {
+                    let text = cell.text_content();
+                    for (column, _) in COMPARED.iter().chain([&("Element", "")]) {
+                        if text.trim().starts_with(column) {
+                            columns.insert(column, i);
+                        }
+                    }
+                }
+                return;
+            }
+            let Some(element_cell) = columns.get("Element").and_then(|&i| cells.get(i)) else {
+                return;
+            };
+            dom_utils::scan_dom(element_cell, &mut |n| {
+                if n.is_html_element(&local_name!("code")) {
+                    let name = n.text_content().trim().to_owned();
+                    row_order.push(name.clone());
+                    rows.insert(name, (tr.clone(), cells.clone()));
+                }
+            });
+        });
+
+        let mut warnings = Vec::new();
+        for (element, dl) in self.dls.iter() {
+            let Some((tr, cells)) = rows.get(&element[..]) else {
+                warnings.push(format!(
+                    "Line {}: <{element}> is missing from the \"List of elements\" index.",
+                    self.line_number(dl)
+                ));
+                continue;
+            };
+            for (column, key) in COMPARED.iter() {
+                let Some(cell) = columns.get(column).and_then(|&i| cells.get(i)) else {
+                    continue;
+                };
+                let Some(dds) = dl
+                    .children
+                    .borrow()
+                    .iter()
+                    .find(|c| {
+                        c.is_html_element(&local_name!("dt"))
+                            && c.any_child(|s| s.attribute_is(&data_x(), key))
+                    })
+                    .map(dom_utils::dt_descriptions)
+                else {
+                    continue;
+                };
+                let (in_index, in_dl): (BTreeSet<String>, BTreeSet<String>) = match *column {
+                    "Attributes" => {
+                        let attributes = |terms: Vec<String>| {
+                            terms
+                                .into_iter()
+                                .filter(|t| t.starts_with("attr-"))
+                                .collect()
+                        };
+                        (
+                            attributes(referenced_terms(cell)),
+                            attributes(dds.iter().flat_map(referenced_terms).collect()),
+                        )
+                    }
+                    "Interface" => {
+                        // The definition has either the IDL block, or says
+                        // which interface it uses.
+                        let from_dl = dds.first().and_then(|dd| {
+                            interface_index::defined_interface(dd)
+                                .or_else(|| referenced_terms(dd).into_iter().next())
+                        });
+                        (
+                            referenced_terms(cell).into_iter().take(1).collect(),
+                            from_dl.map(|i| i.to_lowercase()).into_iter().collect(),
+                        )
+                    }
+                    _ => {
+                        let relevant = |terms: Vec<String>| {
+                            terms
+                                .into_iter()
+                                .filter(|t| {
+                                    t.ends_with(" content")
+                                        || (*column != "Categories" && element_names.contains(t))
+                                })
+                                .collect()
+                        };
+                        (
+                            relevant(referenced_terms(cell)),
+                            relevant(dds.iter().flat_map(referenced_terms).collect()),
+                        )
+                    }
+                };
+                if in_index != in_dl {
+                    let only_dl: Vec<&str> = in_dl.difference(&in_index).map(|s| &s[..]).collect();
+                    let only_index: Vec<&str> =
+                        in_index.difference(&in_dl).map(|s| &s[..]).collect();
+                    warnings.push(format!(
+                        "Line {} (definition) and line {} (index): {column} of <{element}> differ; only in definition: [{}]; only in index: [{}].",
+                        self.line_number(dl),
+                        self.line_number(tr),
+                        only_dl.join(", "),
+                        only_index.join(", "),
+                    ));
+                }
+            }
+        }
+        for name in row_order {
+            if !element_names.contains(&name) {
+                let (tr, _) = &rows[&name];
+                warnings.push(format!(
+                    "Line {}: <{name}> is in the \"List of elements\" index but has no <dl class=\"element\">.",
+                    self.line_number(tr)
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_document_async;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_consistency() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<h4>The <dfn element><code>ul</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-categories">Categories</span>:</dt>
+<dd><span>Flow content</span>.</dd>
+<dd>If the element's children include at least one <code>li</code> element: <span>Palpable content</span>.</dd>
+<dt><span data-x="concept-element-contexts">Contexts in which this element can be used</span>:</dt>
+<dd>Where <span>flow content</span> is expected.</dd>
+<dt><span data-x="concept-element-content-model">Content model</span>:</dt>
+<dd>Zero or more <code>li</code> and <span>script-supporting elements</span>.</dd>
+<dt><span data-x="concept-element-attributes">Content attributes</span>:</dt>
+<dd><span>Global attributes</span></dd>
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd><pre><code class="idl">interface <dfn interface>HTMLUListElement</dfn> : <span>HTMLElement</span> {};</code></pre></dd>
+</dl>
+<h4>The <dfn element><code>li</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-contexts">Contexts in which this element can be used</span>:</dt>
+<dd>Inside <code>ol</code> elements.</dd>
+<dd>Inside <code>ul</code> elements.</dd>
+<dt><span data-x="concept-element-attributes">Content attributes</span>:</dt>
+<dd><span>Global attributes</span></dd>
+<dd><code data-x="attr-li-value">value</code></dd>
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd>Uses <code>HTMLLIElement</code>.</dd>
+</dl>
+<h4>The <dfn element><code>ol</code></dfn> element</h4>
+<dl class="element">
+</dl>
+<table>
+<caption>List of elements</caption>
+<thead><tr><th>Element<th>Description<th>Categories<th>Parents†<th>Children<th>Attributes<th>Interface
+<tbody>
+<tr><th><code data-x="">ul</code><td>List<td><span data-x="flow content">flow</span>; <span data-x="palpable content">palpable</span><td><span data-x="flow content">flow</span><td><code>li</code>; <span data-x="script-supporting elements">script-supporting</span><td><span data-x="global attributes">globals</span><td><code>HTMLUListElement</code>
+<tr><th><code data-x="">li</code><td>List item<td>none<td><code>ol</code><td><span data-x="flow content">flow</span><td><span data-x="global attributes">globals</span>; <code data-x="attr-li-value">value</code>; <code data-x="attr-li-type">type</code><td><code>HTMLElement</code>
+<tr><th><code data-x="">marquee</code><td>Scrolling<td><td><td><td><td>
+</table>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.warnings(),
+            vec![
+                "Line 17 (definition) and line 35 (index): Parents of <li> differ; only in definition: [ul]; only in index: [].",
+                "Line 17 (definition) and line 35 (index): Attributes of <li> differ; only in definition: []; only in index: [attr-li-type].",
+                "Line 17 (definition) and line 35 (index): Interface of <li> differ; only in definition: [htmllielement]; only in index: [htmlelement].",
+                "Line 28: <ol> is missing from the \"List of elements\" index.",
+                "Line 36: <marquee> is in the \"List of elements\" index but has no <dl class=\"element\">.",
+            ]
+        );
+        Ok(())
+    }
+}
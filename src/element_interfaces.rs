@@ -0,0 +1,248 @@
+//! Checks that the interface in each element's "DOM interface" is defined
+//! exactly once and inherits from HTMLElement, and that each HTML...Element
+//! interface is the interface of some element, or is inherited by one which
+//! is. Interfaces which are deliberately not attached to an element, such as
+//! those of obsolete elements, are exempt.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::LazyLock;
+
+use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::Handle;
+use regex::Regex;
+
+use crate::dom_utils::{self, NodeHandleExt};
+use crate::interface_index::{self, IdlBlock};
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, format_warnings};
+use crate::webidl::DefinitionKind;
+
+/// Matches the name of an interface for an HTML element.
+static ELEMENT_INTERFACE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^HTML\w+Element$").unwrap());
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// Finds the <dl class="element"> for each element.
+    element_dls: dom_utils::ElementDlTracker,
+
+    /// The <dl class="element"> blocks, with the elements they describe.
+    dls: Vec<(Vec<StrTendril>, Handle)>,
+}
+
+/// HTML...Element interfaces which are intentionally not the interface of any
+/// element defined with a <dl class="element">: the one for unknown elements,
+/// and those of obsolete elements.
+const UNATTACHED_INTERFACES: &[&str] = &[
+    "HTMLUnknownElement",
+    "HTMLDirectoryElement",
+    "HTMLFontElement",
+    "HTMLFrameElement",
+    "HTMLFrameSetElement",
+    "HTMLMarqueeElement",
+    "HTMLParamElement",
+];
+
+/// An interface definition (not a partial one).
+struct Interface {
+    inherits: Option<String>,
+    line: Option<u64>,
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            element_dls: Default::default(),
+            dls: Vec::new(),
+        }
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if let Some(elements) = self.element_dls.visit(node) {
+            self.dls.push((elements, node.clone()));
+        }
+    }
+
+    /// Returns the interface named in the "DOM interface" of the element
+    /// definition: either the one its IDL defines, or the one it uses.
+    fn declared_interface(dl: &Handle) -> Option<String> {
+        let data_x = QualName::new(None, ns!(), LocalName::from("data-x"));
+        let dt = dl
+            .children
+            .borrow()
+            .iter()
+            .find(|c| {
+                c.is_html_element(&local_name!("dt"))
+                    && c.any_child(|s| s.attribute_is(&data_x, "concept-element-dom"))
+            })
+            .cloned()?;
+        let dd = dom_utils::dt_descriptions(&dt).into_iter().next()?;
+        if let Some(name) = interface_index::defined_interface(&dd) {
+            return Some(name);
+        }
+        let code = dd
+            .children
+            .borrow()
+            .iter()
+            .find(|c| c.is_html_element(&local_name!("code")))
+            .cloned()?;
+        Some(code.text_content().trim().to_owned())
+    }
+
+    /// Returns a description of each problem with the elements' interfaces,
+    /// given the IDL blocks as parsed by interface_index. Should be called
+    /// after visiting the document.
+    pub fn warnings(&self, blocks: &[IdlBlock]) -> Vec<String> {
+        // Syntax errors are reported by idl_validation, so blocks which don't
+        // parse are just skipped here.
+        let mut interfaces: BTreeMap<&str, Vec<Interface>> = BTreeMap::new();
+        for block in blocks.iter() {
+            let Ok(ref definitions) = block.definitions else {
+                continue;
+            };
+            let start = self.parsed.line_number_for(&block.node);
+            for definition in definitions.iter() {
+                if definition.kind == DefinitionKind::Interface && !definition.partial {
+                    interfaces
+                        .entry(&definition.name)
+                        .or_default()
+                        .push(Interface {
+                            inherits: definition.inherits.clone(),
+                            line: start.map(|ln| ln + definition.line - 1),
+                        });
+                }
+            }
+        }
+
+        let mut warnings: Vec<(Option<u64>, String)> = Vec::new();
+        let mut used = HashSet::new();
+        for (elements, dl) in self.dls.iter() {
+            let line = self.parsed.line_number_for(dl);
+            let element = &elements[0];
+            let Some(name) = Self::declared_interface(dl) else {
+                warnings.push((line, format!("<{element}> has no DOM interface.")));
+                continue;
+            };
+            used.insert(name.clone());
+            let definitions = interfaces.get(&name[..]).map_or(&[][..], |d| &d[..]);
+            if definitions.len() != 1 {
+                warnings.push((
+                    line,
+                    format!(
+                        "The interface of <{element}>, {name}, is defined {} times, expected once.",
+                        definitions.len()
+                    ),
+                ));
+                continue;
+            }
+
+            // Follow the inheritance chain, guarding against cycles.
+            let mut ancestor = name.clone();
+            let mut seen = HashSet::new();
+            while ancestor != "HTMLElement" && seen.insert(ancestor.clone()) {
+                match interfaces
+                    .get(&ancestor[..])
+                    .and_then(|d| d.first())
+                    .and_then(|i| i.inherits.clone())
+                {
+                    Some(parent) => {
+                        // Interfaces such as HTMLMediaElement are associated with
+                        // elements through the interfaces which inherit from them.
+                        used.insert(parent.clone());
+                        ancestor = parent;
+                    }
+                    None => break,
+                }
+            }
+            if ancestor != "HTMLElement" {
+                warnings.push((
+                    line,
+                    format!(
+                        "The interface of <{element}>, {name}, does not inherit from HTMLElement."
+                    ),
+                ));
+            }
+        }
+
+        for (name, definitions) in interfaces.iter() {
+            if ELEMENT_INTERFACE_RE.is_match(name)
+                && !used.contains(*name)
+                && !UNATTACHED_INTERFACES.contains(name)
+            {
+                warnings.push((
+                    definitions[0].line,
+                    format!("{name} is not the interface of any element."),
+                ));
+            }
+        }
+
+        format_warnings(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_document_async;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_warnings() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<pre><code class="idl">[Exposed=Window]
+interface <dfn interface>HTMLElement</dfn> : <span>Element</span> {};
+[Exposed=Window]
+interface <dfn interface>HTMLMediaElement</dfn> : <span>HTMLElement</span> {};
+[Exposed=Window]
+interface <dfn interface>HTMLBlinkElement</dfn> : <span>HTMLElement</span> {};
+[Exposed=Window]
+interface <dfn interface>HTMLUnknownElement</dfn> : <span>HTMLElement</span> {};
+[Exposed=Window]
+interface <dfn interface>HTMLFontElement</dfn> : <span>HTMLElement</span> {};</code></pre>
+<h4>The <dfn element><code>video</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd><pre><code class="idl">[Exposed=Window]
+interface <dfn interface>HTMLVideoElement</dfn> : <span>HTMLMediaElement</span> {};</code></pre></dd>
+</dl>
+<h4>The <dfn element><code>b</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd>Uses <code>HTMLElement</code>.</dd>
+</dl>
+<h4>The <dfn element><code>marquee</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd>Uses <code>HTMLMarqueeElement</code>.</dd>
+</dl>
+<h4>The <dfn element><code>svg</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd><pre><code class="idl">[Exposed=Window]
+interface <dfn interface>SVGThingElement</dfn> : <span>Element</span> {};</code></pre></dd>
+</dl>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut index = interface_index::Processor::new();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| {
+            index.visit(h);
+            proc.visit(h);
+        });
+        assert_eq!(
+            proc.warnings(index.idl_blocks()),
+            vec![
+                "Line 6: HTMLBlinkElement is not the interface of any element.",
+                "Line 24: The interface of <marquee>, HTMLMarqueeElement, is defined 0 times, expected once.",
+                "Line 29: The interface of <svg>, SVGThingElement, does not inherit from HTMLElement.",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,229 @@
+//! Gathers what the spec says about each element, from its
+//! <dl class="element"> and its "represents" paragraph, so that it can be
+//! written out as JSON. This should be run on the preprocessed document, so that
+//! tag omission rules and content attribute descriptions have been filled in.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::{Handle, NodeData};
+use serde::Serialize;
+
+use crate::dom_utils::{self, NodeHandleExt, capitalize};
+use crate::represents;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct ElementMetadata {
+    pub name: String,
+    pub categories: Vec<String>,
+    pub contexts: Vec<String>,
+    pub content_model: Vec<String>,
+    pub tag_omission: Vec<String>,
+    pub content_attributes: Vec<ContentAttribute>,
+    pub dom_interface: Vec<String>,
+
+    /// The rest of the "The <code>x</code> element represents ..." sentence.
+    pub represents: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ContentAttribute {
+    /// The attribute name, or the text of the entry if it isn't about a single
+    /// attribute (e.g., "Global attributes").
+    pub name: String,
+
+    /// The data-x of the attribute's definition, if it links to one.
+    pub key: Option<String>,
+    pub description: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Processor {
+    /// Finds the <dl class="element"> for each element.
+    element_dls: dom_utils::ElementDlTracker,
+
+    /// The <dl class="element"> blocks, with the elements they describe.
+    dls: Vec<(Vec<StrTendril>, Handle)>,
+
+    /// Map from tag name to the <span> which contains the text "represents".
+    represents: HashMap<StrTendril, Handle>,
+}
+
+/// Returns the data-x attribute of `node` or its first descendant which has one.
+fn first_data_x(node: &Handle) -> Option<StrTendril> {
+    let data_x = QualName::new(None, ns!(), LocalName::from("data-x"));
+    let mut found = None;
+    dom_utils::scan_dom(node, &mut |n| {
+        if found.is_none() {
+            found = n.get_attribute(&data_x);
+        }
+    });
+    found
+}
+
+/// Returns the text content, with runs of whitespace collapsed.
+fn normalized_text(node: &Handle) -> String {
+    node.text_content()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl Processor {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if let Some(elements) = self.element_dls.visit(node) {
+            self.dls.push((elements, node.clone()));
+        }
+        if let NodeData::Text { ref contents } = node.data
+            && contents.borrow().as_ref() == "represents"
+            && let Some((tag, span)) = represents::find_tag_name(node)
+        {
+            self.represents.entry(tag).or_insert(span);
+        }
+    }
+
+    fn represents_summary(&self, tag: &StrTendril) -> Option<String> {
+        let span = self.represents.get(tag)?;
+        let p = span.parent_node()?;
+        let text: String = p
+            .children
+            .borrow()
+            .iter()
+            .skip_while(|s| !Rc::ptr_eq(s, span))
+            .skip(1)
+            .map(|n| n.text_content().to_string())
+            .collect();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        Some(capitalize(&text).to_string())
+    }
+
+    /// Returns the metadata for each element, in document order.
+    pub fn extract(self) -> Vec<ElementMetadata> {
+        let mut result = Vec::new();
+        for (elements, dl) in self.dls.iter() {
+            let mut metadata = ElementMetadata::default();
+            let mut key: Option<StrTendril> = None;
+            for child in dl.children.borrow().iter() {
+                if child.is_html_element(&local_name!("dt")) {
+                    key = first_data_x(child);
+                    continue;
+                }
+                if !child.is_html_element(&local_name!("dd")) {
+                    continue;
+                }
+                let text = normalized_text(child);
+                match key.as_deref() {
+                    Some("concept-element-categories") => metadata.categories.push(text),
+                    Some("concept-element-contexts") => metadata.contexts.push(text),
+                    Some("concept-element-content-model") => metadata.content_model.push(text),
+                    Some("concept-element-tag-omission") => metadata.tag_omission.push(text),
+                    Some("concept-element-dom") => metadata.dom_interface.push(text),
+                    Some("concept-element-attributes") => {
+                        let (name, description) = match text.split_once('—') {
+                            Some((name, description)) => {
+                                (name.trim().to_owned(), Some(description.trim().to_owned()))
+                            }
+                            None => (text, None),
+                        };
+                        metadata.content_attributes.push(ContentAttribute {
+                            name,
+                            key: first_data_x(child).map(|k| k.to_string()),
+                            description,
+                        });
+                    }
+                    _ => (),
+                }
+            }
+            for element in elements {
+                result.push(ElementMetadata {
+                    name: element.to_string(),
+                    represents: self.represents_summary(element),
+                    ..metadata.clone()
+                });
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_document_async;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_extract() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<h4>The <dfn element><code>a</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-categories">Categories</span>:</dt>
+<dd><span>Flow content</span>.</dd>
+<dd><span>Phrasing content</span>.</dd>
+<dt><span data-x="concept-element-contexts">Contexts in which this element can be used</span>:</dt>
+<dd>Where <span>phrasing content</span> is expected.</dd>
+<dt><span data-x="concept-element-content-model">Content model</span>:</dt>
+<dd><span>Transparent</span>.</dd>
+<dt><span data-x="concept-element-tag-omission">Tag omission in text/html</span>:</dt>
+<dd>Neither tag is omissible.</dd>
+<dt><span data-x="concept-element-attributes">Content attributes</span>:</dt>
+<dd><span data-x="global-attributes">Global attributes</span></dd>
+<dd><code data-x="attr-hyperlink-href">href</code>
+ — Address of the <span>hyperlink</span></dd>
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd><code>HTMLAnchorElement</code></dd>
+</dl>
+<p>The <code>a</code> element <span>represents</span> a
+<span>hyperlink</span>.
+<h4>The <dfn element><code>sub</code></dfn> and <dfn element><code>sup</code></dfn> elements</h4>
+<dl class="element">
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd><code>HTMLElement</code></dd>
+</dl>
+<p>The <code>sup</code> element <span>represents</span> a superscript."#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let elements = proc.extract();
+        assert_eq!(
+            elements[0],
+            ElementMetadata {
+                name: "a".to_owned(),
+                categories: vec!["Flow content.".to_owned(), "Phrasing content.".to_owned()],
+                contexts: vec!["Where phrasing content is expected.".to_owned()],
+                content_model: vec!["Transparent.".to_owned()],
+                tag_omission: vec!["Neither tag is omissible.".to_owned()],
+                content_attributes: vec![
+                    ContentAttribute {
+                        name: "Global attributes".to_owned(),
+                        key: Some("global-attributes".to_owned()),
+                        description: None,
+                    },
+                    ContentAttribute {
+                        name: "href".to_owned(),
+                        key: Some("attr-hyperlink-href".to_owned()),
+                        description: Some("Address of the hyperlink".to_owned()),
+                    },
+                ],
+                dom_interface: vec!["HTMLAnchorElement".to_owned()],
+                represents: Some("A hyperlink.".to_owned()),
+            }
+        );
+        let names: Vec<_> = elements.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["a", "sub", "sup"]);
+        assert_eq!(elements[1].dom_interface, ["HTMLElement"]);
+        assert_eq!(elements[1].represents, None);
+        assert_eq!(elements[2].represents.as_deref(), Some("A superscript."));
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+//! Checks the "List of event handler content attributes" table in the index,
+//! which is maintained by hand, against the event handler IDL attributes of the
+//! GlobalEventHandlers and WindowEventHandlers mixins and against the "List of
+//! events" table.
+
+use std::collections::{BTreeMap, HashSet};
+
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::Handle;
+
+use crate::dom_utils::{self, NodeHandleExt};
+use crate::interface_index::IdlBlock;
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, format_warnings};
+use crate::webidl::{DefinitionKind, MemberKind};
+
+/// The mixins whose event handler IDL attributes have content attributes.
+const MIXINS: [&str; 2] = ["GlobalEventHandlers", "WindowEventHandlers"];
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// The "List of event handler content attributes" table, if found.
+    handler_table: Option<Handle>,
+
+    /// The "List of events" table, if found.
+    events_table: Option<Handle>,
+
+    /// Events defined by other specifications, which aren't in the index.
+    external_events: HashSet<String>,
+}
+
+fn data_x() -> QualName {
+    QualName::new(None, ns!(), LocalName::from("data-x"))
+}
+
+/// Returns the rows of the table body, with their cells.
+fn body_rows(table: &Handle) -> Vec<(Handle, Vec<Handle>)> {
+    let mut rows = Vec::new();
+    dom_utils::scan_dom(table, &mut |tr| {
+        if tr.is_html_element(&local_name!("tr"))
+            && tr
+                .parent_node()
+                .is_some_and(|p| p.is_html_element(&local_name!("tbody")))
+        {
+            let cells = tr
+                .children
+                .borrow()
+                .iter()
+                .filter(|c| {
+                    c.is_html_element(&local_name!("th")) || c.is_html_element(&local_name!("td"))
+                })
+                .cloned()
+                .collect();
+            rows.push((tr.clone(), cells));
+        }
+    });
+    rows
+}
+
+/// Returns the text of the first <code> in the cell, or otherwise of the cell.
+fn cell_name(cell: &Handle) -> String {
+    let mut name = None;
+    dom_utils::scan_dom(cell, &mut |n| {
+        if name.is_none() && n.is_html_element(&local_name!("code")) {
+            name = Some(n.text_content());
+        }
+    });
+    name.unwrap_or_else(|| cell.text_content())
+        .trim()
+        .to_owned()
+}
+
+/// Returns the event which the description cell links to, if any.
+fn linked_event(cell: &Handle) -> Option<String> {
+    let mut event = None;
+    dom_utils::scan_dom(cell, &mut |n| {
+        if event.is_none()
+            && n.get_attribute(&data_x())
+                .is_some_and(|d| d.starts_with("event-"))
+        {
+            event = Some(n.text_content().trim().to_owned());
+        }
+    });
+    event
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            handler_table: None,
+            events_table: None,
+            external_events: HashSet::new(),
+        }
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if node.is_html_element(&local_name!("caption"))
+            && let Some(table) = node
+                .parent_node()
+                .filter(|t| t.is_html_element(&local_name!("table")))
+        {
+            match node.text_content().trim() {
+                "List of event handler content attributes" => self.handler_table = Some(table),
+                "List of events" => self.events_table = Some(table),
+                _ => (),
+            }
+        }
+        // Events from other specifications are defined with a link to them.
+        if node.is_html_element(&local_name!("dfn"))
+            && node
+                .get_attribute(&data_x())
+                .is_some_and(|d| d.starts_with("event-"))
+            && node.has_attribute(&QualName::new(None, ns!(), LocalName::from("data-x-href")))
+        {
+            self.external_events
+                .insert(node.text_content().trim().to_owned());
+        }
+    }
+
+    /// Returns a description of each problem with the event handler content
+    /// attributes table. Should be called after visiting the document, with
+    /// the document's parsed IDL blocks.
+    pub fn warnings(&self, idl_blocks: &[IdlBlock]) -> Vec<String> {
+        let Some(ref handler_table) = self.handler_table else {
+            return Vec::new();
+        };
+        let mut warnings: Vec<(Option<u64>, String)> = Vec::new();
+
+        // Map from each event handler IDL attribute to its mixin and line. The
+        // mixins may be split across partial definitions.
+        let mut mixin_found = false;
+        let mut idl_attributes: BTreeMap<&str, (&str, Option<u64>)> = BTreeMap::new();
+        for block in idl_blocks {
+            let Ok(ref definitions) = block.definitions else {
+                continue;
+            };
+            let start = self.parsed.line_number_for(&block.node);
+            for definition in definitions.iter().filter(|d| {
+                d.kind == DefinitionKind::InterfaceMixin && MIXINS.contains(&d.name.as_str())
+            }) {
+                mixin_found = true;
+                for member in definition.members.iter() {
+                    let (MemberKind::Attribute, Some(name), Some(type_name)) =
+                        (member.kind, &member.name, &member.type_name)
+                    else {
+                        continue;
+                    };
+                    if type_name.ends_with("EventHandler") {
+                        idl_attributes
+                            .entry(name)
+                            .or_insert((&definition.name, start.map(|ln| ln + member.line - 1)));
+                    }
+                }
+            }
+        }
+
+        let events: Option<HashSet<String>> = self.events_table.as_ref().map(|table| {
+            body_rows(table)
+                .iter()
+                .filter_map(|(_, cells)| cells.first().map(cell_name))
+                .collect()
+        });
+
+        let mut content_attributes = HashSet::new();
+        for (tr, cells) in body_rows(handler_table) {
+            let Some(name) = cells.first().map(cell_name) else {
+                continue;
+            };
+            let line = self.parsed.line_number_for(&tr);
+            if mixin_found && !idl_attributes.contains_key(name.as_str()) {
+                warnings.push((
+                    line,
+                    format!(
+                        "Event handler content attribute {name} has no event handler IDL attribute in {}.",
+                        MIXINS.join(" or ")
+                    ),
+                ));
+            }
+            if let Some(ref events) = events {
+                let event = cells
+                    .get(2)
+                    .and_then(linked_event)
+                    .or_else(|| name.strip_prefix("on").map(|e| e.to_owned()))
+                    .unwrap_or_default();
+                if !events.contains(&event) && !self.external_events.contains(&event) {
+                    warnings.push((
+                        line,
+                        format!(
+                            "Event handler content attribute {name} is for the {event} event, which is not in the \"List of events\" index."
+                        ),
+                    ));
+                }
+            }
+            content_attributes.insert(name);
+        }
+
+        for (name, (mixin, line)) in idl_attributes {
+            if !content_attributes.contains(name) {
+                warnings.push((
+                    line,
+                    format!(
+                        "Event handler IDL attribute {name} of {mixin} is not in the \"List of event handler content attributes\" index."
+                    ),
+                ));
+            }
+        }
+
+        format_warnings(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::interface_index;
+    use crate::parser::parse_document_async;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_warnings() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<ul>
+<li><dfn data-x="event-click" data-x-href="https://w3c.github.io/uievents/#event-type-click"><code>click</code></dfn> event
+</ul>
+<pre><code class="idl">interface mixin <dfn interface>GlobalEventHandlers</dfn> {
+  attribute <span>EventHandler</span> <span data-x="handler-onabort">onabort</span>;
+  attribute <span>EventHandler</span> <span data-x="handler-onclick">onclick</span>;
+  attribute <span>OnErrorEventHandler</span> <span data-x="handler-onerror">onerror</span>;
+};</code></pre>
+<pre><code class="idl">partial interface mixin <dfn interface>WindowEventHandlers</dfn> {
+  [LegacyLenientThis] attribute <span>EventHandler</span> <span data-x="handler-window-onafterprint">onafterprint</span>;
+};</code></pre>
+<table>
+<caption>List of events</caption>
+<thead><tr><th>Event<th>Interface<th>Interesting targets<th>Description
+<tbody>
+<tr><td><code data-x="event-abort">abort</code><td><code>Event</code><td><code>Window</code><td>Fired when loading is aborted
+<tr><td><code data-x="event-error">error</code><td><code>Event</code><td><code>Window</code><td>Fired when there's an error
+</table>
+<table>
+<caption>List of event handler content attributes</caption>
+<thead><tr><th>Attribute<th>Element(s)<th>Description<th>Value
+<tbody>
+<tr><th><code data-x="">onabort</code><td><span data-x="handler-onabort">HTML elements</span><td><code data-x="event-abort">abort</code> event handler<td><span>Event handler content attribute</span>
+<tr><th><code data-x="">onafterprint</code><td><code>body</code><td><code data-x="event-afterprint">afterprint</code> event handler for <code>Window</code> object<td><span>Event handler content attribute</span>
+<tr><th><code data-x="">onclick</code><td><span data-x="handler-onclick">HTML elements</span><td><code data-x="event-click">click</code> event handler<td><span>Event handler content attribute</span>
+<tr><th><code data-x="">onmarquee</code><td><span>HTML elements</span><td><code data-x="event-marquee">marquee</code> event handler<td><span>Event handler content attribute</span>
+</table>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut index = interface_index::Processor::new();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| {
+            index.visit(h);
+            proc.visit(h);
+        });
+        assert_eq!(
+            proc.warnings(index.idl_blocks()),
+            vec![
+                "Line 8: Event handler IDL attribute onerror of GlobalEventHandlers is not in the \"List of event handler content attributes\" index.",
+                "Line 25: Event handler content attribute onafterprint is for the afterprint event, which is not in the \"List of events\" index.",
+                "Line 27: Event handler content attribute onmarquee has no event handler IDL attribute in GlobalEventHandlers or WindowEventHandlers.",
+                "Line 27: Event handler content attribute onmarquee is for the marquee event, which is not in the \"List of events\" index.",
+            ]
+        );
+        Ok(())
+    }
+}
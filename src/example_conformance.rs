@@ -0,0 +1,231 @@
+//! Checks that inline markup examples, i.e. `<pre><code class="html">`, parse
+//! without errors, so that they never contradict the parsing rules they
+//! illustrate. Examples marked `class="bad"` (on the `<pre>` or the `<code>`)
+//! are intentionally non-conforming and are skipped.
+//!
+//! Examples starting with a doctype are parsed as documents, and those starting
+//! with `<html>`, `<head>` or `<body>` as documents with the doctype implied.
+//! Others are parsed as fragments, in the context of the element that their
+//! first start tag implies (e.g. `<tbody>` for `<tr>`), or of the element named
+//! by `data-context` on the `<pre>` or `<code>`.
+
+use std::io;
+use std::sync::LazyLock;
+
+use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::Handle;
+use regex::Regex;
+
+use crate::dom_utils::NodeHandleExt;
+use crate::parser;
+use crate::rcdom_with_line_numbers::RcDomWithLineNumbers;
+
+/// Classes which mark an example as deliberately containing errors.
+const SKIP_CLASSES: &[&str] = &["bad"];
+
+/// Matches the first start tag of an example, capturing its name.
+static START_TAG_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<([A-Za-z][A-Za-z0-9-]*)").unwrap());
+
+/// How an example is parsed.
+enum Context {
+    /// As a document, with a doctype prepended if it's implied.
+    Document { implied_doctype: bool },
+
+    /// As a fragment, in the context of an element with this name.
+    Fragment(LocalName),
+}
+
+impl Context {
+    /// Picks the context for an example, unless one is given explicitly.
+    fn for_example(text: &str, explicit: Option<StrTendril>) -> Self {
+        if let Some(name) = explicit {
+            return Context::Fragment(LocalName::from(name.to_ascii_lowercase()));
+        }
+        if text
+            .trim_start()
+            .get(..9)
+            .is_some_and(|s| s.eq_ignore_ascii_case("<!doctype"))
+        {
+            return Context::Document {
+                implied_doctype: false,
+            };
+        }
+        let first_tag = START_TAG_RE
+            .captures(text)
+            .map(|c| c[1].to_ascii_lowercase())
+            .unwrap_or_default();
+        let context = match &first_tag[..] {
+            "html" | "head" | "body" => {
+                return Context::Document {
+                    implied_doctype: true,
+                };
+            }
+            "tr" => local_name!("tbody"),
+            "td" | "th" => local_name!("tr"),
+            "caption" | "colgroup" | "thead" | "tbody" | "tfoot" => local_name!("table"),
+            "col" => local_name!("colgroup"),
+            "option" | "optgroup" => local_name!("select"),
+            _ => local_name!("body"),
+        };
+        Context::Fragment(context)
+    }
+}
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// The text of each example, with the line its <code> element starts on
+    /// and how it is to be parsed.
+    examples: Vec<(Option<u64>, StrTendril, Context)>,
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            examples: Vec::new(),
+        }
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if !node.is_html_element(&local_name!("code")) || !node.has_class("html") {
+            return;
+        }
+        let pre = match node
+            .parent_node()
+            .filter(|p| p.is_html_element(&local_name!("pre")))
+        {
+            Some(pre) => pre,
+            None => return,
+        };
+        if node.has_any_class(SKIP_CLASSES) || pre.has_any_class(SKIP_CLASSES) {
+            return;
+        }
+        let text = node.text_content();
+        // Examples loaded from a file are checked by the boilerplate processor.
+        if text.trim_start().starts_with("EXAMPLE ") {
+            return;
+        }
+        let data_context = QualName::new(None, ns!(), LocalName::from("data-context"));
+        let explicit = node
+            .get_attribute(&data_context)
+            .or_else(|| pre.get_attribute(&data_context));
+        let context = Context::for_example(&text, explicit);
+        self.examples
+            .push((self.parsed.line_number_for(node), text, context));
+    }
+
+    /// Returns a description of each parse error in the examples. Should be
+    /// called after visiting the document.
+    pub async fn warnings(&self) -> io::Result<Vec<String>> {
+        let mut warnings = Vec::new();
+        for (code_line, text, context) in self.examples.iter() {
+            let dom = match context {
+                Context::Document { implied_doctype } => {
+                    // The doctype goes on the first line, so that line numbers
+                    // are unaffected.
+                    let text = if *implied_doctype {
+                        format!("<!DOCTYPE html>{text}")
+                    } else {
+                        text.to_string()
+                    };
+                    parser::parse_document_unchecked_async(text.as_bytes()).await?
+                }
+                Context::Fragment(name) => {
+                    let context = Handle::create_element(name.clone()).build();
+                    parser::parse_fragment_unchecked_async(text[..].as_bytes(), &context).await?
+                }
+            };
+            for (line, msg) in dom.located_parse_errors() {
+                // The example's first line is the one its <code> starts on.
+                match code_line {
+                    Some(code_line) => warnings.push(format!(
+                        "Line {}: Parse error in markup example: {msg}",
+                        code_line + line - 1
+                    )),
+                    None => warnings.push(format!(
+                        "Line {line} of a markup example: Parse error: {msg}"
+                    )),
+                }
+            }
+        }
+        Ok(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom_utils;
+    use crate::parser::parse_document_async;
+
+    #[tokio::test]
+    async fn test_valid_examples() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<pre><code class="html">&lt;ul>
+ &lt;li>One
+ &lt;li>Two
+&lt;/ul></code></pre>
+<pre><code class="html">&lt;!DOCTYPE html>
+&lt;title>Document&lt;/title>
+&lt;p>Hello</code></pre>
+<pre class="bad"><code class="html">&lt;b>&lt;i>x&lt;/b>&lt;/i></code></pre>
+<pre><code class="html bad">&lt;b>&lt;i>x&lt;/b>&lt;/i></code></pre>
+<pre><code class="js">if (a &lt;/b> b) {}</code></pre>
+<pre><code class="html">&lt;tr>&lt;td>1&lt;td>2
+&lt;tr>&lt;td>3&lt;td>4</code></pre>
+<pre><code class="html">&lt;td>A cell&lt;/td></code></pre>
+<pre><code class="html">&lt;option>One&lt;option>Two</code></pre>
+<pre><code class="html">&lt;html lang="en">
+ &lt;head>&lt;title>Implied doctype&lt;/title>&lt;/head>
+ &lt;body>...&lt;/body>
+&lt;/html></code></pre>
+<pre data-context="tbody"><code class="html">&lt;script>addRows();&lt;/script>&lt;tr>&lt;td>x</code></pre>
+"#
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(proc.warnings().await?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_errors_mapped_to_source_lines() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<p>Some prose.</p>
+<pre><code class="html">&lt;p>
+  &lt;b>&lt;i>bad nesting&lt;/b>&lt;/i>
+&lt;/p></code></pre>
+<pre><code class="html">&lt;!DOCTYPE html>
+&lt;title>Doc&lt;/title>
+
+&lt;p>&amp;bogus;</code></pre>
+"#
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let warnings = proc.warnings().await?;
+        assert_eq!(warnings.len(), 3, "{warnings:?}");
+        assert!(
+            warnings[0].starts_with("Line 4: Parse error in markup example: "),
+            "{warnings:?}"
+        );
+        assert!(warnings[1].starts_with("Line 4: "), "{warnings:?}");
+        assert!(
+            warnings[2].starts_with("Line 9: ") && warnings[2].contains("&bogus;"),
+            "{warnings:?}"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,312 @@
+//! Produces a unified diff which adds IDs to start tags in the source, so that
+//! IDs derived during the build (see self_link) can be made permanent.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// The number of unchanged lines shown around each change.
+const CONTEXT: usize = 3;
+
+/// Matches a comment, or a start tag, capturing its name and its attributes.
+static TOKEN_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"(?s:<!--.*?-->)|<([A-Za-z][A-Za-z0-9-]*)((?:\s+[^\s"'>/=]+(?:\s*=\s*(?:"[^"]*"|'[^']*'|[^\s"'>]+))?)*)\s*/?>"#,
+    )
+    .unwrap()
+});
+
+/// Elements whose contents are text, so that anything which looks like a tag
+/// in them isn't one.
+const TEXT_ELEMENTS: [&str; 5] = ["script", "style", "textarea", "title", "xmp"];
+
+/// Matches an attribute, capturing its name and its value.
+static ATTRIBUTE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([^\s"'>/=]+)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#).unwrap()
+});
+
+/// An ID to add to the start tag of an element.
+pub struct Insertion {
+    /// The line the element was reported on, which is the line its start tag
+    /// ends on.
+    pub line: u64,
+
+    /// The element's tag name.
+    pub tag: String,
+
+    /// The element's class attribute.
+    pub class: String,
+
+    pub id: String,
+}
+
+/// A start tag in the source.
+struct StartTag {
+    name: String,
+
+    /// The offset just after the tag name, where an ID is added.
+    name_end: usize,
+    class: String,
+    has_id: bool,
+}
+
+/// Returns a patch against `path`, whose contents are `source`, which adds
+/// the IDs, along with a message for each start tag which couldn't be found.
+pub fn patch(path: &str, source: &str, insertions: &[Insertion]) -> (String, Vec<String>) {
+    let lines: Vec<&str> = source.split_inclusive('\n').collect();
+
+    // Find the start tags, keyed by the line they end on, skipping comments
+    // and the contents of text elements.
+    let mut tags: HashMap<u64, Vec<StartTag>> = HashMap::new();
+    let mut line = 1;
+    let mut counted = 0;
+    let mut position = 0;
+    while let Some(captures) = TOKEN_RE.captures_at(source, position) {
+        let end = captures.get(0).unwrap().end();
+        position = end;
+        let Some(name) = captures.get(1) else {
+            continue;
+        };
+        line += source[counted..end].matches('\n').count() as u64;
+        counted = end;
+        let mut tag = StartTag {
+            name: name.as_str().to_ascii_lowercase(),
+            name_end: name.end(),
+            class: String::new(),
+            has_id: false,
+        };
+        for attribute in ATTRIBUTE_RE.captures_iter(&captures[2]) {
+            match attribute[1].to_ascii_lowercase().as_str() {
+                "id" => tag.has_id = true,
+                "class" => {
+                    tag.class = (2..=4)
+                        .find_map(|i| attribute.get(i))
+                        .map_or("", |v| v.as_str())
+                        .to_owned()
+                }
+                _ => (),
+            }
+        }
+        if TEXT_ELEMENTS.contains(&tag.name.as_str()) {
+            let end_tag = format!("</{}", tag.name);
+            position = source.as_bytes()[end..]
+                .windows(end_tag.len())
+                .position(|w| w.eq_ignore_ascii_case(end_tag.as_bytes()))
+                .map_or(source.len(), |i| end + i);
+        }
+        tags.entry(line).or_default().push(tag);
+    }
+
+    // Add each ID to the first start tag without one on the element's line
+    // with the same name and classes.
+    let mut additions: BTreeMap<usize, String> = BTreeMap::new();
+    let mut warnings = Vec::new();
+    for insertion in insertions {
+        let found = tags.get_mut(&insertion.line).and_then(|tags| {
+            tags.iter_mut().find(|tag| {
+                !tag.has_id
+                    && tag.name.eq_ignore_ascii_case(&insertion.tag)
+                    && tag
+                        .class
+                        .split_whitespace()
+                        .eq(insertion.class.split_whitespace())
+            })
+        });
+        match found {
+            Some(tag) => {
+                tag.has_id = true;
+                additions.insert(
+                    tag.name_end,
+                    format!(" id=\"{}\"", escape_attribute_value(&insertion.id)),
+                );
+            }
+            None => warnings.push(format!(
+                "Line {}: Could not find the <{}> start tag to add the ID {} to.",
+                insertion.line, insertion.tag, insertion.id
+            )),
+        }
+    }
+
+    // Work out the new text of each changed line.
+    let mut changed: BTreeMap<usize, String> = BTreeMap::new();
+    let mut start = 0;
+    for (i, line) in lines.iter().enumerate() {
+        let end = start + line.len();
+        let mut new = String::new();
+        let mut copied = start;
+        for (&at, addition) in additions.range(start..end) {
+            new.push_str(&source[copied..at]);
+            new.push_str(addition);
+            copied = at;
+        }
+        if copied != start {
+            new.push_str(&source[copied..end]);
+            changed.insert(i, new);
+        }
+        start = end;
+    }
+
+    if changed.is_empty() {
+        return (String::new(), warnings);
+    }
+
+    // Group the changed lines into hunks, merging those whose context overlaps.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for &i in changed.keys() {
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + CONTEXT + 1).min(lines.len());
+        match hunks.last_mut() {
+            Some(hunk) if hunk.1 >= start => hunk.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut out = format!("--- a/{path}\n+++ b/{path}\n");
+    for (start, end) in hunks {
+        let len = end - start;
+        out.push_str(&format!(
+            "@@ -{},{len} +{},{len} @@\n",
+            start + 1,
+            start + 1
+        ));
+        for (i, line) in lines.iter().enumerate().take(end).skip(start) {
+            match changed.get(&i) {
+                Some(new) => {
+                    push_line(&mut out, '-', line);
+                    push_line(&mut out, '+', new);
+                }
+                None => push_line(&mut out, ' ', line),
+            }
+        }
+    }
+    (out, warnings)
+}
+
+/// Escapes a value for a double-quoted attribute.
+fn escape_attribute_value(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;")
+}
+
+fn push_line(out: &mut String, prefix: char, line: &str) {
+    out.push(prefix);
+    out.push_str(line);
+    if !line.ends_with('\n') {
+        out.push_str("\n\\ No newline at end of file\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch() {
+        let source = "<h4 id=\"the-a-element\">The a element</h4>\n\
+            <div class=\"example\">\n\
+            <p>One.\n\
+            </div>\n\
+            <p>Some prose.\n\
+            <p>More prose.\n\
+            <p>Yet more prose.\n\
+            <p>Still more prose.\n\
+            <p>Even more prose.\n\
+            <div class=\"note\"><p>A note.</div> <div\n\
+            \x20class=\"example\"><p>Two.</div>\n\
+            <div><div class=\"example\"><p>Three.</div></div>";
+        let insertions = [
+            Insertion {
+                line: 2,
+                tag: "div".to_owned(),
+                class: "example".to_owned(),
+                id: "the-a-element:example-1".to_owned(),
+            },
+            Insertion {
+                line: 10,
+                tag: "div".to_owned(),
+                class: "note".to_owned(),
+                id: "the-a-element:note-1".to_owned(),
+            },
+            Insertion {
+                line: 11,
+                tag: "div".to_owned(),
+                class: "example".to_owned(),
+                id: "the-a-element:example-2".to_owned(),
+            },
+            Insertion {
+                line: 12,
+                tag: "div".to_owned(),
+                class: "example".to_owned(),
+                id: "the-a-element:example-3".to_owned(),
+            },
+            Insertion {
+                line: 4,
+                tag: "section".to_owned(),
+                class: String::new(),
+                id: "missing".to_owned(),
+            },
+        ];
+        let (patch, warnings) = patch("source", source, &insertions);
+        assert_eq!(
+            patch,
+            r#"--- a/source
++++ b/source
+@@ -1,5 +1,5 @@
+ <h4 id="the-a-element">The a element</h4>
+-<div class="example">
++<div id="the-a-element:example-1" class="example">
+ <p>One.
+ </div>
+ <p>Some prose.
+@@ -7,6 +7,6 @@
+ <p>Yet more prose.
+ <p>Still more prose.
+ <p>Even more prose.
+-<div class="note"><p>A note.</div> <div
++<div id="the-a-element:note-1" class="note"><p>A note.</div> <div id="the-a-element:example-2"
+  class="example"><p>Two.</div>
+-<div><div class="example"><p>Three.</div></div>
+\ No newline at end of file
++<div><div id="the-a-element:example-3" class="example"><p>Three.</div></div>
+\ No newline at end of file
+"#
+        );
+        assert_eq!(
+            warnings,
+            vec!["Line 4: Could not find the <section> start tag to add the ID missing to."]
+        );
+    }
+
+    #[test]
+    fn test_patch_skips_comments_and_text() {
+        let source = "<!-- <div class=\"example\"> --><div class=\"example\"><p>One.</div>\n\
+            <script>document.write('<div class=\"example\">');</SCRIPT><div class=\"example\">\n";
+        let insertions = [
+            Insertion {
+                line: 1,
+                tag: "div".to_owned(),
+                class: "example".to_owned(),
+                id: "a&b:example-1".to_owned(),
+            },
+            Insertion {
+                line: 2,
+                tag: "div".to_owned(),
+                class: "example".to_owned(),
+                id: "\"quoted\":example-2".to_owned(),
+            },
+        ];
+        let (patch, warnings) = patch("source", source, &insertions);
+        assert_eq!(
+            patch,
+            r#"--- a/source
++++ b/source
+@@ -1,2 +1,2 @@
+-<!-- <div class="example"> --><div class="example"><p>One.</div>
++<!-- <div class="example"> --><div id="a&amp;b:example-1" class="example"><p>One.</div>
+-<script>document.write('<div class="example">');</SCRIPT><div class="example">
++<script>document.write('<div class="example">');</SCRIPT><div id="&quot;quoted&quot;:example-2" class="example">
+"#
+        );
+        assert!(warnings.is_empty());
+    }
+}
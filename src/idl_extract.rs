@@ -0,0 +1,92 @@
+//! Concatenates the spec's IDL blocks into a single IDL file, for consumers
+//! such as webref and WPT's idlharness which don't want to scrape the built
+//! spec. Each block is preceded by a comment giving its line in the source.
+
+use markup5ever_rcdom::Handle;
+
+use crate::dom_utils::NodeHandleExt;
+use crate::interface_index::is_idl_block;
+use crate::rcdom_with_line_numbers::RcDomWithLineNumbers;
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// The <code class="idl"> blocks, in document order.
+    blocks: Vec<Handle>,
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            blocks: Vec::new(),
+        }
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if is_idl_block(node) {
+            self.blocks.push(node.clone());
+        }
+    }
+
+    /// Returns the IDL, with the blocks separated by blank lines.
+    pub fn extract(&self) -> String {
+        self.blocks
+            .iter()
+            .map(|block| {
+                let line = self
+                    .parsed
+                    .line_number_for(block)
+                    .map(|ln| ln.to_string())
+                    .unwrap_or_else(|| "?".to_owned());
+                format!("// Source line {line}\n{}", block.text_content().trim_end())
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dom_utils;
+    use crate::parser::parse_document_async;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_extract() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<pre><code class="idl">[<span>Exposed</span>=Window]
+interface <dfn interface>HTMLMarqueeElement</dfn> : <span>HTMLElement</span> {
+  attribute DOMString <span>behavior</span>;
+};
+</code></pre>
+<pre class="extract"><code class="idl">interface Example {};</code></pre>
+<p>Some prose.
+<pre><code class="idl">partial interface <span id="Window-partial">Window</span> {
+  undefined <span>blink</span>();
+};</code></pre>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.extract(),
+            r#"// Source line 2
+[Exposed=Window]
+interface HTMLMarqueeElement : HTMLElement {
+  attribute DOMString behavior;
+};
+
+// Source line 9
+partial interface Window {
+  undefined blink();
+};"#
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+//! Reports syntax errors in the IDL blocks, as parsed by interface_index, and
+//! problems which only show up across the whole spec: duplicate members,
+//! references to types which aren't defined, interfaces without [Exposed], and
+//! partials and includes statements whose target isn't defined.
+
+use std::collections::{HashMap, HashSet};
+
+use html5ever::local_name;
+use markup5ever_rcdom::Handle;
+
+use crate::dom_utils::NodeHandleExt;
+use crate::interface_index::IdlBlock;
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, format_warnings};
+use crate::webidl::{Definition, DefinitionKind, MemberKind};
+
+/// Identifies a member: the kind and name of its definition, and its name.
+type MemberKey<'d> = (DefinitionKind, &'d str, &'d str);
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// The text of every <dfn>, which includes the types defined by other
+    /// specifications.
+    dfns: HashSet<String>,
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            dfns: HashSet::new(),
+        }
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if node.is_html_element(&local_name!("dfn")) {
+            self.dfns.insert(node.text_content().trim().to_owned());
+        }
+    }
+
+    /// Returns a description of each problem with the IDL blocks, as parsed
+    /// by interface_index. Should be called after visiting the document.
+    pub fn warnings(&self, blocks: &[IdlBlock]) -> Vec<String> {
+        let mut warnings: Vec<(Option<u64>, String)> = Vec::new();
+
+        // Collect every block's definitions, making line numbers relative to
+        // the source.
+        let mut definitions: Vec<Definition> = Vec::new();
+        for block in blocks.iter() {
+            let Some(start) = self.parsed.line_number_for(&block.node) else {
+                continue;
+            };
+            let absolute = |line: u64| start + line - 1;
+            match block.definitions {
+                Ok(ref parsed) => definitions.extend(parsed.iter().cloned().map(|mut d| {
+                    d.line = absolute(d.line);
+                    for member in d.members.iter_mut() {
+                        member.line = absolute(member.line);
+                    }
+                    for reference in d.references.iter_mut() {
+                        reference.line = absolute(reference.line);
+                    }
+                    d
+                })),
+                Err(ref e) => {
+                    warnings.push((Some(absolute(e.line)), format!("IDL syntax error: {e}")))
+                }
+            }
+        }
+
+        let defined: HashSet<&str> = definitions
+            .iter()
+            .filter(|d| !d.partial && d.kind != DefinitionKind::Includes)
+            .map(|d| &d.name[..])
+            .collect();
+        let is_known = |name: &str| defined.contains(name) || self.dfns.contains(name);
+
+        // Members of the same definition (including its partials) which share
+        // a name, other than overloaded operations.
+        let mut members: HashMap<MemberKey, Vec<(MemberKind, u64)>> = HashMap::new();
+        for definition in definitions.iter() {
+            for member in definition.members.iter() {
+                let Some(ref name) = member.name else {
+                    continue;
+                };
+                let seen = members
+                    .entry((definition.kind, &definition.name, name))
+                    .or_default();
+                if let Some(&(_, first_line)) = seen.first()
+                    && (member.kind != MemberKind::Operation
+                        || seen.iter().any(|(kind, _)| *kind != MemberKind::Operation))
+                {
+                    warnings.push((
+                        Some(member.line),
+                        format!(
+                            "Duplicate member {name} of {} (first on line {first_line}).",
+                            definition.name
+                        ),
+                    ));
+                }
+                seen.push((member.kind, member.line));
+            }
+        }
+
+        for definition in definitions.iter() {
+            let line = Some(definition.line);
+            for reference in definition.references.iter() {
+                if !is_known(&reference.name) {
+                    warnings.push((
+                        Some(reference.line),
+                        format!("Unknown type {} in IDL.", reference.name),
+                    ));
+                }
+            }
+            if let Some(ref parent) = definition.inherits
+                && !is_known(parent)
+            {
+                warnings.push((
+                    line,
+                    format!("{} inherits from unknown {parent}.", definition.name),
+                ));
+            }
+            match definition.kind {
+                DefinitionKind::Interface | DefinitionKind::Namespace
+                    if !definition.partial
+                        && definition.extended_attribute("Exposed").is_none() =>
+                {
+                    warnings.push((
+                        line,
+                        format!("{} has no [Exposed] extended attribute.", definition.name),
+                    ));
+                }
+                _ if definition.partial && !is_known(&definition.name) => {
+                    warnings.push((
+                        line,
+                        format!(
+                            "Partial {} has no corresponding definition.",
+                            definition.name
+                        ),
+                    ));
+                }
+                DefinitionKind::Includes => {
+                    for name in [&definition.name]
+                        .into_iter()
+                        .chain(definition.includes.as_ref())
+                    {
+                        if !is_known(name) {
+                            warnings.push((
+                                line,
+                                format!("Includes statement refers to unknown {name}."),
+                            ));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        format_warnings(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_document_async;
+    use crate::{dom_utils, interface_index};
+    use std::io;
+
+    #[tokio::test]
+    async fn test_warnings() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<p>The <dfn data-x-href="https://dom.spec.whatwg.org/#interface-element"><code>Element</code></dfn> interface.
+<pre><code class="idl">[<span>Exposed</span>=Window]
+interface <dfn interface>HTMLMarqueeElement</dfn> : <span>HTMLElement</span> {
+  attribute DOMString <span>behavior</span>;
+  undefined <span>start</span>();
+  undefined <span>start</span>(long delay);
+  attribute <span>MarqueeDirection</span> direction;
+};
+interface <dfn interface>HTMLBlinkElement</dfn> : <span>Element</span> {
+  attribute <span>Element</span> target;
+};</code></pre>
+<pre><code class="idl">partial interface <span>HTMLMarqueeElement</span> {
+  readonly attribute boolean <span>behavior</span>;
+};
+partial interface <span>Ghost</span> {};
+<span>HTMLBlinkElement</span> includes <span>Blinking</span>;</code></pre>
+<pre><code class="idl">interface Broken {
+  attribute DOMString;
+};</code></pre>
+<pre class="extract"><code class="idl">interface Example { ... };</code></pre>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut index = interface_index::Processor::new();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| {
+            index.visit(h);
+            proc.visit(h);
+        });
+        assert_eq!(
+            proc.warnings(index.idl_blocks()),
+            vec![
+                "Line 3: HTMLMarqueeElement inherits from unknown HTMLElement.",
+                "Line 8: Unknown type MarqueeDirection in IDL.",
+                "Line 10: HTMLBlinkElement has no [Exposed] extended attribute.",
+                "Line 14: Duplicate member behavior of HTMLMarqueeElement (first on line 5).",
+                "Line 16: Partial Ghost has no corresponding definition.",
+                "Line 17: Includes statement refers to unknown Blinking.",
+                "Line 19: IDL syntax error: Expected an identifier, found \";\".",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,101 +1,286 @@
-//! Generates an index of WebIDL interfaces.
+//! Generates an index of the WebIDL definitions, grouped by kind: interfaces,
+//! then interface mixins, callback interfaces, callback functions,
+//! dictionaries, enumerations, typedefs and namespaces.
 //! This index is inserted where "INSERT INTERFACES HERE" appears.
+//!
+//! Optionally, an index of the interfaces exposed in each global, from their
+//! [Exposed] extended attributes, is inserted where "INSERT INTERFACES BY
+//! GLOBAL HERE" appears. The same grouping can be extracted as JSON.
+//! Interfaces without [Exposed] are left out; idl_validation reports them.
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::io;
 
 use html5ever::tendril::StrTendril;
 use html5ever::{QualName, local_name, ns};
 use markup5ever_rcdom::Handle;
 
-use crate::dom_utils::NodeHandleExt;
+use crate::dom_utils::{self, NodeHandleExt};
+use crate::webidl::{self, Definition, DefinitionKind, ParseError};
 
 #[derive(Default, Debug)]
-struct InterfaceInfo {
-    /// Number of times the interface definition was seen. Should be one.
+struct DefinitionInfo {
+    /// The kind of definition, from the definition itself or else from a
+    /// partial definition.
+    kind: Option<DefinitionKind>,
+
+    /// Number of times the definition was seen. Should be one.
     /// We store other numbers for convenience in error handling and reporting.
     seen: u32,
 
-    /// The IDs of the partial interfaces, in the order they appear in the document.
+    /// The IDs of the partial definitions, in the order they appear in the document.
     partials: Vec<StrTendril>,
 
     /// Set to true if a partial is missing its ID.
     has_partial_with_no_id: bool,
+
+    /// For mixins, the interfaces which include them, with the ID of the
+    /// includes statement if it has one.
+    included_by: Vec<(StrTendril, Option<StrTendril>)>,
+
+    /// For interfaces, the globals in their [Exposed] extended attribute, if
+    /// it has one.
+    exposed: Option<Vec<String>>,
 }
 
+#[derive(Default)]
 pub struct Processor {
-    /// The interfaces encountered, keyed and sorted by name.
-    interfaces: BTreeMap<StrTendril, InterfaceInfo>,
+    /// The definitions encountered, keyed and sorted by name.
+    definitions: BTreeMap<StrTendril, DefinitionInfo>,
 
     /// The text nodes which contains the text "INSERT INTERFACES HERE".
     marker_nodes: Vec<Handle>,
+
+    /// The text nodes which contain the text "INSERT INTERFACES BY GLOBAL HERE".
+    exposure_marker_nodes: Vec<Handle>,
+
+    /// The globals named by [Global] and [Exposed] extended attributes, which
+    /// are those that [Exposed=*] stands for.
+    globals: BTreeSet<String>,
+
+    /// The IDL blocks, in document order.
+    idl_blocks: Vec<IdlBlock>,
+}
+
+/// A <code class="idl"> block, with its definitions or the syntax error which
+/// prevented them from being parsed. Line numbers are relative to the block.
+pub struct IdlBlock {
+    pub node: Handle,
+    pub definitions: Result<Vec<Definition>, ParseError>,
 }
 
 /// The string which marks where the index belongs. Ideally this would be a node
 /// and not plain text.
 const MARKER: &str = "INSERT INTERFACES HERE";
 
+/// The string which marks where the index by global belongs.
+const EXPOSURE_MARKER: &str = "INSERT INTERFACES BY GLOBAL HERE";
+
+/// The kinds of definition which are indexed, in order, with how each group
+/// and each definition are labeled. Interfaces come first, without a label.
+const GROUPS: [(DefinitionKind, &str, &str); 8] = [
+    (DefinitionKind::Interface, "", "Interface"),
+    (
+        DefinitionKind::InterfaceMixin,
+        "Interface mixins",
+        "Interface mixin",
+    ),
+    (
+        DefinitionKind::CallbackInterface,
+        "Callback interfaces",
+        "Callback interface",
+    ),
+    (
+        DefinitionKind::CallbackFunction,
+        "Callback functions",
+        "Callback function",
+    ),
+    (DefinitionKind::Dictionary, "Dictionaries", "Dictionary"),
+    (DefinitionKind::Enum, "Enumerations", "Enumeration"),
+    (DefinitionKind::Typedef, "Typedefs", "Typedef"),
+    (DefinitionKind::Namespace, "Namespaces", "Namespace"),
+];
+
+/// Returns whether the node is a <code class="idl"> block which is part of the
+/// spec's IDL, i.e., is inside a <pre> which isn't <pre class="extract">.
+pub fn is_idl_block(node: &Handle) -> bool {
+    node.is_html_element(&local_name!("code"))
+        && node.has_class("idl")
+        && node
+            .parent_node()
+            .is_some_and(|p| p.is_html_element(&local_name!("pre")) && !p.has_class("extract"))
+}
+
+/// Returns the name of the interface defined (not just partially) by the first
+/// IDL block within the node, if there is one, e.g., in the "DOM interface" of
+/// an element definition.
+pub fn defined_interface(node: &Handle) -> Option<String> {
+    let mut block = None;
+    dom_utils::scan_dom(node, &mut |n| {
+        if block.is_none() && is_idl_block(n) {
+            block = Some(n.clone());
+        }
+    });
+    webidl::parse(&block?.text_content())
+        .ok()?
+        .into_iter()
+        .find(|d| d.kind == DefinitionKind::Interface && !d.partial)
+        .map(|d| d.name)
+}
+
 impl Processor {
     pub fn new() -> Self {
         Processor {
-            interfaces: BTreeMap::new(),
+            definitions: BTreeMap::new(),
             marker_nodes: Vec::new(),
+            exposure_marker_nodes: Vec::new(),
+            globals: BTreeSet::new(),
+            idl_blocks: Vec::new(),
         }
     }
 
     pub fn visit(&mut self, node: &Handle) {
-        const ID: QualName = QualName {
-            prefix: None,
-            ns: ns!(),
-            local: local_name!("id"),
-        };
         // We're looking for <code class="idl"> inside a <pre>, to find
-        // potential interfaces defined there.
+        // potential definitions there.
         //
         // One surprise here -- there is an "interface Example" that is not defined
         // according to Wattsi. It yells about this not being defined, and the
         // prior Perl preprocessing actually requires the <pre> have no
         // attributes.
-        if node.is_html_element(&local_name!("code"))
-            && node.has_class("idl")
-            && node
-                .parent_node()
-                .is_some_and(|p| p.is_html_element(&local_name!("pre")) && !p.has_class("extract"))
+        //
+        // The blocks are kept, parsed, for idl_validation and
+        // element_interfaces. idl_validation reports syntax errors with their
+        // line; since a block which doesn't parse would be missing from the
+        // index and from its duplicate checks, apply() then fails.
+        if is_idl_block(node) {
+            let definitions = webidl::parse(&node.text_content());
+            if let Ok(ref definitions) = definitions {
+                self.index(node, definitions);
+            }
+            self.idl_blocks.push(IdlBlock {
+                node: node.clone(),
+                definitions,
+            });
+        }
+
+        if node.node_text().is_some_and(|t| t.contains(MARKER)) {
+            self.marker_nodes.push(node.clone());
+        }
+        if node
+            .node_text()
+            .is_some_and(|t| t.contains(EXPOSURE_MARKER))
         {
-            let borrowed_children = node.children.borrow();
-            for window in borrowed_children.windows(2) {
-                let is_partial = match window[0].node_text() {
-                    Some(a) if a.ends_with("partial interface ") => true,
-                    Some(a) if a.ends_with("interface ") => false,
-                    _ => continue,
-                };
-                // These definitions must appear as a <span>, <dfn> or <a> element.
-                if !window[1].is_html_element(&local_name!("span"))
-                    && !window[1].is_html_element(&local_name!("dfn"))
-                    && !window[1].is_html_element(&local_name!("a"))
-                {
+            self.exposure_marker_nodes.push(node.clone());
+        }
+    }
+
+    /// Records the definitions in an IDL block.
+    fn index(&mut self, node: &Handle, definitions: &[Definition]) {
+        const ID: QualName = QualName {
+            prefix: None,
+            ns: ns!(),
+            local: local_name!("id"),
+        };
+        // The IDs of the <span>, <dfn> and <a> elements which name
+        // definitions, keyed by where their text starts in the block.
+        let mut ids: HashMap<usize, Option<StrTendril>> = HashMap::new();
+        let mut offset = 0;
+        for child in node.children.borrow().iter() {
+            if child.is_html_element(&local_name!("span"))
+                || child.is_html_element(&local_name!("dfn"))
+                || child.is_html_element(&local_name!("a"))
+            {
+                ids.insert(offset, child.get_attribute(&ID));
+            }
+            offset += child.text_content().chars().count();
+        }
+        let id_at = |offset: usize| ids.get(&offset).cloned().flatten();
+
+        for definition in definitions {
+            let name = StrTendril::from(&definition.name[..]);
+            if definition.kind == DefinitionKind::Includes {
+                let (Some(mixin), Some(offset)) =
+                    (&definition.includes, definition.includes_offset)
+                else {
                     continue;
+                };
+                let info = self.definitions.entry(mixin[..].into()).or_default();
+                info.included_by.push((name, id_at(offset)));
+                continue;
+            }
+            let info = self.definitions.entry(name).or_default();
+            if definition.partial {
+                info.kind.get_or_insert(definition.kind);
+                match id_at(definition.name_offset) {
+                    Some(id) => info.partials.push(id),
+                    None => info.has_partial_with_no_id = true,
                 }
-                let name = window[1].text_content();
-                let info = self.interfaces.entry(name).or_default();
-                if is_partial {
-                    if let Some(id) = window[1].get_attribute(&ID) {
-                        info.partials.push(id);
-                    } else {
-                        info.has_partial_with_no_id = true;
+            } else {
+                info.kind = Some(definition.kind);
+                info.seen += 1;
+                if definition.kind == DefinitionKind::Interface {
+                    info.exposed = definition
+                        .extended_attribute("Exposed")
+                        .map(|ea| ea.values.clone());
+                    self.globals
+                        .extend(info.exposed.iter().flatten().filter(|g| *g != "*").cloned());
+                    if let Some(global) = definition.extended_attribute("Global") {
+                        if global.values.is_empty() {
+                            self.globals.insert(definition.name.clone());
+                        }
+                        self.globals.extend(global.values.iter().cloned());
                     }
-                } else {
-                    info.seen += 1;
                 }
             }
         }
+    }
 
-        if node.node_text().is_some_and(|t| t.contains(MARKER)) {
-            self.marker_nodes.push(node.clone());
+    /// Returns the IDL blocks, in document order, for the checks which need
+    /// their definitions.
+    pub fn idl_blocks(&self) -> &[IdlBlock] {
+        &self.idl_blocks
+    }
+
+    /// Returns the interfaces exposed in each global, keyed and sorted by
+    /// global. Interfaces exposed everywhere are listed under every global.
+    pub fn exposure(&self) -> BTreeMap<String, Vec<String>> {
+        let mut by_global: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (name, info) in &self.definitions {
+            if info.kind != Some(DefinitionKind::Interface) || info.seen == 0 {
+                continue;
+            }
+            for global in info.exposed.iter().flatten() {
+                let globals = if global == "*" {
+                    self.globals.iter().collect()
+                } else {
+                    vec![global]
+                };
+                for global in globals {
+                    by_global
+                        .entry(global.clone())
+                        .or_default()
+                        .push(name.to_string());
+                }
+            }
         }
+        by_global
     }
 
     pub fn apply(self) -> io::Result<()> {
+        if let Some(e) = self
+            .idl_blocks
+            .iter()
+            .find_map(|b| b.definitions.as_ref().err())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "IDL syntax error on line {} of an IDL block, which would be left out of the interface index: {e}",
+                    e.line
+                ),
+            ));
+        }
+
         // It is likely an author error to not include anywhere to insert an
         // interface index. More than one is supported, mainly because it's no
         // more work than enforcing that just one exists.
@@ -114,70 +299,142 @@ impl Processor {
                 ),
             ));
         }
-        for marker in self.marker_nodes {
-            // We need to find where the marker appears in the text so that we
-            // can split it into two text nodes.
-            let text = marker.node_text().expect("should still be a text node");
-            let position: u32 = match text.find(MARKER) {
-                None => {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("Marker {MARKER:?} not found (but was during first pass)."),
-                    ));
+        for marker in self.marker_nodes.iter() {
+            // We need to construct a list of each kind of definition, and
+            // their partial definitions.
+            fn make_link(id: &str, text: &str) -> Handle {
+                Handle::create_element(local_name!("a"))
+                    .attribute(&local_name!("href"), format!("#{id}"))
+                    .text(text)
+                    .build()
+            }
+            let mut replacements = Vec::new();
+            for (kind, group_label, label) in GROUPS {
+                let mut ul = Handle::create_element(local_name!("ul"))
+                    .attribute(&local_name!("class"), "brief");
+                let mut is_empty = true;
+                for (name, info) in &self.definitions {
+                    let info_kind = info.kind.unwrap_or(if info.included_by.is_empty() {
+                        DefinitionKind::Interface
+                    } else {
+                        DefinitionKind::InterfaceMixin
+                    });
+                    if info_kind != kind {
+                        continue;
+                    }
+                    if info.seen > 1 {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("{label} {name} defined {} times.", info.seen),
+                        ));
+                    }
+                    let mut li = Handle::create_element(local_name!("li")).child(
+                        Handle::create_element(local_name!("code"))
+                            .text(name.clone())
+                            .build(),
+                    );
+                    match &info.partials[..] {
+                        [] => (),
+                        [sole_partial] => {
+                            li = li.text(", ").child(make_link(sole_partial, "partial"));
+                        }
+                        [first, rest @ ..] => {
+                            li = li.text(", ").child(make_link(first, "partial 1"));
+                            for (i, p) in rest.iter().enumerate() {
+                                li = li.text(" ").child(make_link(p, &(i + 2).to_string()));
+                            }
+                        }
+                    }
+                    for (i, (interface, id)) in info.included_by.iter().enumerate() {
+                        li = li.text(if i == 0 { ", included by " } else { ", " });
+                        li = match id {
+                            Some(id) => li.child(make_link(id, interface)),
+                            None => li.text(interface.clone()),
+                        };
+                    }
+                    ul = ul.child(li.build());
+                    is_empty = false;
                 }
-                Some(p) => p.try_into().unwrap(),
-            };
-            let end_position: u32 = position + TryInto::<u32>::try_into(MARKER.len()).unwrap();
-            let before = text.subtendril(0, position);
-            let after = text.subtendril(end_position, text.len32() - end_position);
-
-            // Then, we need to construct a list of interfaces and their partial interfaces.
-            let mut ul =
-                Handle::create_element(local_name!("ul")).attribute(&local_name!("class"), "brief");
-            for (name, info) in &self.interfaces {
-                if info.seen > 1 {
-                    return Err(io::Error::new(
-                        io::ErrorKind::InvalidData,
-                        format!("Interface {name} defined {} times.", info.seen),
-                    ));
+                if is_empty && kind != DefinitionKind::Interface {
+                    continue;
                 }
-                fn make_link(id: &str, text: &str) -> Handle {
-                    Handle::create_element(local_name!("a"))
-                        .attribute(&local_name!("href"), format!("#{id}"))
-                        .text(text)
-                        .build()
+                if !group_label.is_empty() {
+                    replacements.push(
+                        Handle::create_element(local_name!("p"))
+                            .text(format!("{group_label}:"))
+                            .build(),
+                    );
                 }
-                let mut li = Handle::create_element(local_name!("li")).child(
-                    Handle::create_element(local_name!("code"))
-                        .text(name.clone())
+                replacements.push(ul.build());
+            }
+            replace_marker(marker, MARKER, replacements)?;
+        }
+
+        // The index by global is optional.
+        if self.exposure_marker_nodes.len() > 1 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "{EXPOSURE_MARKER:?} found {} times, expected at most one.",
+                    self.exposure_marker_nodes.len()
+                ),
+            ));
+        }
+        if let Some(marker) = self.exposure_marker_nodes.first() {
+            let mut replacements = Vec::new();
+            for (global, interfaces) in self.exposure() {
+                replacements.push(
+                    Handle::create_element(local_name!("p"))
+                        .text(format!("{global}:"))
                         .build(),
                 );
-                match &info.partials[..] {
-                    [] => (),
-                    [sole_partial] => {
-                        li = li.text(", ").child(make_link(sole_partial, "partial"));
-                    }
-                    [first, rest @ ..] => {
-                        li = li.text(", ").child(make_link(first, "partial 1"));
-                        for (i, p) in rest.iter().enumerate() {
-                            li = li.text(" ").child(make_link(p, &(i + 2).to_string()));
-                        }
-                    }
+                let mut ul = Handle::create_element(local_name!("ul"))
+                    .attribute(&local_name!("class"), "brief");
+                for interface in interfaces {
+                    ul = ul.child(
+                        Handle::create_element(local_name!("li"))
+                            .child(
+                                Handle::create_element(local_name!("code"))
+                                    .text(interface)
+                                    .build(),
+                            )
+                            .build(),
+                    );
                 }
-                ul = ul.child(li.build());
+                replacements.push(ul.build());
             }
-
-            // Finally, we replace the marker's text node with the combination of the two.
-            marker.replace_with(vec![
-                Handle::create_text_node(before),
-                ul.build(),
-                Handle::create_text_node(after),
-            ]);
+            replace_marker(marker, EXPOSURE_MARKER, replacements)?;
         }
         Ok(())
     }
 }
 
+/// Replaces the marker text within the text node with the given nodes.
+fn replace_marker(marker: &Handle, marker_text: &str, nodes: Vec<Handle>) -> io::Result<()> {
+    // We need to find where the marker appears in the text so that we
+    // can split it into two text nodes.
+    let text = marker.node_text().expect("should still be a text node");
+    let position: u32 = match text.find(marker_text) {
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Marker {marker_text:?} not found (but was during first pass)."),
+            ));
+        }
+        Some(p) => p.try_into().unwrap(),
+    };
+    let end_position: u32 = position + TryInto::<u32>::try_into(marker_text.len()).unwrap();
+    let before = text.subtendril(0, position);
+    let after = text.subtendril(end_position, text.len32() - end_position);
+
+    // Finally, we replace the marker's text node with the combination of them.
+    let mut replacements = vec![Handle::create_text_node(before)];
+    replacements.extend(nodes);
+    replacements.push(Handle::create_text_node(after));
+    marker.replace_with(replacements);
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -190,8 +447,8 @@ mod tests {
             r#"
 <!DOCTYPE html>
 <pre><code class=idl>
-interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
-interface <dfn interface>HTMLBlinkElement</dfn> { ... }
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
+interface <dfn interface>HTMLBlinkElement</dfn> {};
 </code></pre>
 INSERT INTERFACES HERE
             "#
@@ -207,8 +464,8 @@ INSERT INTERFACES HERE
             serialize_for_test(&[document]),
             r#"
 <!DOCTYPE html><html><head></head><body><pre><code class="idl">
-interface <dfn interface="">HTMLMarqueeElement</dfn> { ... }
-interface <dfn interface="">HTMLBlinkElement</dfn> { ... }
+interface <dfn interface="">HTMLMarqueeElement</dfn> {};
+interface <dfn interface="">HTMLBlinkElement</dfn> {};
 </code></pre>
 <ul class="brief"><li><code>HTMLBlinkElement</code></li><li><code>HTMLMarqueeElement</code></li></ul></body></html>
             "#.trim());
@@ -221,10 +478,10 @@ interface <dfn interface="">HTMLBlinkElement</dfn> { ... }
             r#"
 <!DOCTYPE html>
 <pre><code class=idl>
-interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
 </code></pre>
 <pre><code class=idl>
-interface <dfn interface>HTMLBlinkElement</dfn> { ... }
+interface <dfn interface>HTMLBlinkElement</dfn> {};
 </code></pre>
 INSERT INTERFACES HERE
             "#
@@ -240,10 +497,10 @@ INSERT INTERFACES HERE
             serialize_for_test(&[document]),
             r#"
 <!DOCTYPE html><html><head></head><body><pre><code class="idl">
-interface <dfn interface="">HTMLMarqueeElement</dfn> { ... }
+interface <dfn interface="">HTMLMarqueeElement</dfn> {};
 </code></pre>
 <pre><code class="idl">
-interface <dfn interface="">HTMLBlinkElement</dfn> { ... }
+interface <dfn interface="">HTMLBlinkElement</dfn> {};
 </code></pre>
 <ul class="brief"><li><code>HTMLBlinkElement</code></li><li><code>HTMLMarqueeElement</code></li></ul></body></html>
             "#.trim());
@@ -256,10 +513,10 @@ interface <dfn interface="">HTMLBlinkElement</dfn> { ... }
             r#"
 <!DOCTYPE html>
 <pre><code class=idl>
-interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
 </code></pre>
 <pre><code class=idl>
-partial interface <span id=HTMLMarqueeElement-partial>HTMLMarqueeElement</span> { ... }
+partial interface <span id=HTMLMarqueeElement-partial>HTMLMarqueeElement</span> {};
 </code></pre>
 INSERT INTERFACES HERE
             "#
@@ -275,10 +532,10 @@ INSERT INTERFACES HERE
             serialize_for_test(&[document]),
             r##"
 <!DOCTYPE html><html><head></head><body><pre><code class="idl">
-interface <dfn interface="">HTMLMarqueeElement</dfn> { ... }
+interface <dfn interface="">HTMLMarqueeElement</dfn> {};
 </code></pre>
 <pre><code class="idl">
-partial interface <span id="HTMLMarqueeElement-partial">HTMLMarqueeElement</span> { ... }
+partial interface <span id="HTMLMarqueeElement-partial">HTMLMarqueeElement</span> {};
 </code></pre>
 <ul class="brief"><li><code>HTMLMarqueeElement</code>, <a href="#HTMLMarqueeElement-partial">partial</a></li></ul></body></html>
             "##.trim());
@@ -291,9 +548,9 @@ partial interface <span id="HTMLMarqueeElement-partial">HTMLMarqueeElement</span
             r#"
 <!DOCTYPE html>
 <pre><code class=idl>
-interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
-partial interface <span id=HTMLMarqueeElement-partial>HTMLMarqueeElement</span> { ... }
-partial interface <span id=HTMLMarqueeElement-partial-2>HTMLMarqueeElement</span> { ... }
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
+partial interface <span id=HTMLMarqueeElement-partial>HTMLMarqueeElement</span> {};
+partial interface <span id=HTMLMarqueeElement-partial-2>HTMLMarqueeElement</span> {};
 </code></pre>
 INSERT INTERFACES HERE
             "#
@@ -309,9 +566,9 @@ INSERT INTERFACES HERE
             serialize_for_test(&[document]),
             r##"
 <!DOCTYPE html><html><head></head><body><pre><code class="idl">
-interface <dfn interface="">HTMLMarqueeElement</dfn> { ... }
-partial interface <span id="HTMLMarqueeElement-partial">HTMLMarqueeElement</span> { ... }
-partial interface <span id="HTMLMarqueeElement-partial-2">HTMLMarqueeElement</span> { ... }
+interface <dfn interface="">HTMLMarqueeElement</dfn> {};
+partial interface <span id="HTMLMarqueeElement-partial">HTMLMarqueeElement</span> {};
+partial interface <span id="HTMLMarqueeElement-partial-2">HTMLMarqueeElement</span> {};
 </code></pre>
 <ul class="brief"><li><code>HTMLMarqueeElement</code>, <a href="#HTMLMarqueeElement-partial">partial 1</a> <a href="#HTMLMarqueeElement-partial-2">2</a></li></ul></body></html>
             "##.trim());
@@ -324,8 +581,8 @@ partial interface <span id="HTMLMarqueeElement-partial-2">HTMLMarqueeElement</sp
             r#"
 <!DOCTYPE html>
 <pre><code class=idl>
-partial interface <span id=HTMLMarqueeElement-partial>HTMLMarqueeElement</span> { ... }
-partial interface <span id=HTMLMarqueeElement-partial-2>HTMLMarqueeElement</span> { ... }
+partial interface <span id=HTMLMarqueeElement-partial>HTMLMarqueeElement</span> {};
+partial interface <span id=HTMLMarqueeElement-partial-2>HTMLMarqueeElement</span> {};
 </code></pre>
 INSERT INTERFACES HERE
             "#
@@ -341,8 +598,8 @@ INSERT INTERFACES HERE
             serialize_for_test(&[document]),
             r##"
 <!DOCTYPE html><html><head></head><body><pre><code class="idl">
-partial interface <span id="HTMLMarqueeElement-partial">HTMLMarqueeElement</span> { ... }
-partial interface <span id="HTMLMarqueeElement-partial-2">HTMLMarqueeElement</span> { ... }
+partial interface <span id="HTMLMarqueeElement-partial">HTMLMarqueeElement</span> {};
+partial interface <span id="HTMLMarqueeElement-partial-2">HTMLMarqueeElement</span> {};
 </code></pre>
 <ul class="brief"><li><code>HTMLMarqueeElement</code>, <a href="#HTMLMarqueeElement-partial">partial 1</a> <a href="#HTMLMarqueeElement-partial-2">2</a></li></ul></body></html>
             "##.trim());
@@ -356,7 +613,7 @@ partial interface <span id="HTMLMarqueeElement-partial-2">HTMLMarqueeElement</sp
 <!DOCTYPE html>
 INSERT INTERFACES HERE
 <pre><code class=idl>
-interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
 </code></pre>
             "#
             .trim()
@@ -372,7 +629,7 @@ interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
             r#"
 <!DOCTYPE html><html><head></head><body><ul class="brief"><li><code>HTMLMarqueeElement</code></li></ul>
 <pre><code class="idl">
-interface <dfn interface="">HTMLMarqueeElement</dfn> { ... }
+interface <dfn interface="">HTMLMarqueeElement</dfn> {};
 </code></pre></body></html>
             "#
             .trim()
@@ -412,8 +669,34 @@ interface <dfn interface="">HTMLMarqueeElement</dfn> { ... }
             r#"
 <!DOCTYPE html>
 <pre><code class=idl>
-interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
-interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
+</code></pre>
+            "#
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let result = proc.apply();
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidData));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn syntax_error() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"
+<!DOCTYPE html>
+<div>INSERT INTERFACES HERE</div>
+<pre><code class=idl>
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
+</code></pre>
+<pre><code class=idl>
+interface <dfn interface>HTMLMarqueeElement</dfn> {
+  attribute DOMString;
+};
 </code></pre>
             "#
             .as_bytes(),
@@ -426,4 +709,147 @@ interface <dfn interface>HTMLMarqueeElement</dfn> { ... }
         assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidData));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn other_kinds_of_definition() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"
+<!DOCTYPE html>
+<pre><code class=idl>
+[<span>Exposed</span>=Window]
+interface <dfn interface>HTMLMarqueeElement</dfn> : <span>HTMLElement</span> {
+  attribute <span>MarqueeBehavior</span> behavior;
+};
+<span>HTMLMarqueeElement</span> includes <span id=HTMLMarqueeElement-includes>Scrolling</span>;
+<span>HTMLBlinkElement</span> includes <span>Scrolling</span>;
+interface mixin <dfn interface-mixin>Scrolling</dfn> {};
+// Behaviors.
+enum <dfn enum>MarqueeBehavior</dfn> { "scroll", "slide" };
+dictionary <dfn dictionary>MarqueeOptions</dfn> { long <span>loop</span> = -1; };
+partial dictionary <span id=MarqueeOptions-partial>MarqueeOptions</span> {};
+callback <dfn callback>MarqueeCallback</dfn> = undefined (<span>MarqueeOptions</span> options);
+typedef (<span>HTMLMarqueeElement</span> or <span>HTMLBlinkElement</span>) <dfn typedef>AnnoyingElement</dfn>;
+partial namespace <span id=console-partial>console</span> {};
+</code></pre>
+INSERT INTERFACES HERE
+            "#
+            .trim()
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        proc.apply()?;
+        let serialized = serialize_for_test(&[document]);
+        assert_eq!(
+            &serialized[serialized.find("</pre>").unwrap()..],
+            r##"</pre>
+<ul class="brief"><li><code>HTMLMarqueeElement</code></li></ul><p>Interface mixins:</p><ul class="brief"><li><code>Scrolling</code>, included by <a href="#HTMLMarqueeElement-includes">HTMLMarqueeElement</a>, HTMLBlinkElement</li></ul><p>Callback functions:</p><ul class="brief"><li><code>MarqueeCallback</code></li></ul><p>Dictionaries:</p><ul class="brief"><li><code>MarqueeOptions</code>, <a href="#MarqueeOptions-partial">partial</a></li></ul><p>Enumerations:</p><ul class="brief"><li><code>MarqueeBehavior</code></li></ul><p>Typedefs:</p><ul class="brief"><li><code>AnnoyingElement</code></li></ul><p>Namespaces:</p><ul class="brief"><li><code>console</code>, <a href="#console-partial">partial</a></li></ul></body></html>"##
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn duplicate_dictionary() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"
+<!DOCTYPE html>
+<pre><code class=idl>
+dictionary <dfn dictionary>MarqueeOptions</dfn> {};
+dictionary <dfn dictionary>MarqueeOptions</dfn> {};
+</code></pre>
+INSERT INTERFACES HERE
+            "#
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let result = proc.apply();
+        assert!(
+            matches!(result, Err(e) if e.to_string() == "Dictionary MarqueeOptions defined 2 times.")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn index_by_global() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"
+<!DOCTYPE html>
+<pre><code class=idl>
+[<span>Exposed</span>=Window]
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
+[<span>Exposed</span>=(Window,Worker), <span>Serializable</span>]
+interface <dfn interface>MarqueeEvent</dfn> {};
+[<span>Exposed</span>=*]
+interface <dfn interface>Blink</dfn> {};
+[<span>Global</span>=(Worklet,MarqueeWorklet), <span>Exposed</span>=MarqueeWorklet]
+interface <dfn interface>MarqueeWorkletGlobalScope</dfn> {};
+</code></pre>
+<div>INSERT INTERFACES HERE</div>
+<div>INSERT INTERFACES BY GLOBAL HERE</div>
+            "#
+            .trim()
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let exposure = proc.exposure();
+        assert_eq!(
+            exposure
+                .iter()
+                .map(|(global, interfaces)| (&global[..], interfaces.join(" ")))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "MarqueeWorklet",
+                    "Blink MarqueeWorkletGlobalScope".to_owned()
+                ),
+                ("Window", "Blink HTMLMarqueeElement MarqueeEvent".to_owned()),
+                ("Worker", "Blink MarqueeEvent".to_owned()),
+                ("Worklet", "Blink".to_owned()),
+            ]
+        );
+        proc.apply()?;
+        let serialized = serialize_for_test(&[document]);
+        assert_eq!(
+            &serialized[serialized.rfind("<div>").unwrap()..serialized.find("<p>Window:").unwrap()],
+            r#"<div><p>MarqueeWorklet:</p><ul class="brief"><li><code>Blink</code></li><li><code>MarqueeWorkletGlobalScope</code></li></ul>"#
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn index_by_global_without_exposed() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"
+<!DOCTYPE html>
+<pre><code class=idl>
+[<span>Exposed</span>=Window]
+interface <dfn interface>HTMLMarqueeElement</dfn> {};
+interface <dfn interface>HTMLBlinkElement</dfn> {};
+</code></pre>
+<div>INSERT INTERFACES HERE</div>
+<div>INSERT INTERFACES BY GLOBAL HERE</div>
+            "#
+            .trim()
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new();
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        proc.apply()?;
+        let serialized = serialize_for_test(&[document]);
+        assert_eq!(
+            &serialized[serialized.rfind("<div>").unwrap()..],
+            r#"<div><p>Window:</p><ul class="brief"><li><code>HTMLMarqueeElement</code></li></ul></div></body></html>"#
+        );
+        Ok(())
+    }
 }
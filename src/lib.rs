@@ -0,0 +1,32 @@
+//! The steps which turn the HTML Standard's source into Wattsi's input, and
+//! the checks run along the way. The html-build binary drives them; embedders,
+//! such as the build server, can drive them too, e.g., reading boilerplate from
+//! memory with source_fs::MemoryFs.
+
+pub mod anchor_permanence;
+pub mod annotate_attributes;
+pub mod attribute_metadata;
+pub mod boilerplate;
+pub mod dom_utils;
+pub mod element_index;
+pub mod element_index_check;
+pub mod element_interfaces;
+pub mod element_metadata;
+pub mod event_handlers;
+pub mod example_conformance;
+pub mod id_patch;
+pub mod idl_extract;
+pub mod idl_validation;
+pub mod interface_index;
+mod io_utils;
+pub mod omission_rules;
+pub mod parser;
+pub mod rcdom_with_line_numbers;
+pub mod reflection;
+pub mod represents;
+pub mod self_link;
+pub mod source_fs;
+pub mod tag_omission;
+pub mod transclusion;
+pub mod variables;
+pub mod webidl;
@@ -3,35 +3,45 @@ use std::borrow::Cow;
 use std::default::Default;
 use std::env;
 use std::ffi::OsStr;
-use std::io::{self, BufWriter};
+use std::io::{self, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use url::Url;
 
-use markup5ever_rcdom::SerializableHandle;
-
-mod anchor_permanence;
-mod annotate_attributes;
-mod boilerplate;
-mod dom_utils;
-mod interface_index;
-mod io_utils;
-mod parser;
-mod rcdom_with_line_numbers;
-mod represents;
-mod self_link;
-mod tag_omission;
-mod variables;
+use html_build::rcdom_with_line_numbers::{RcDomWithLineNumbers, line_prefix};
+use html_build::{
+    anchor_permanence, annotate_attributes, attribute_metadata, boilerplate, dom_utils,
+    element_index, element_index_check, element_interfaces, element_metadata, event_handlers,
+    example_conformance, id_patch, idl_extract, idl_validation, interface_index, parser,
+    reflection, represents, self_link, source_fs, tag_omission, transclusion, variables,
+};
+use markup5ever_rcdom::{NodeData, SerializableHandle};
 
 #[tokio::main]
 async fn main() -> io::Result<()> {
-    let is_post = env::args().any(|a| a == "--singlepage-post");
-    let result = if is_post {
-        // --singlepage-post runs the postprocess phase, which is currently only meant to be used on the
-        // singlepage output from Wattsi.
-        run_postprocess().await
-    } else {
-        // By default we run the preprocess phase, which creates a new input for Wattsi.
-        run_preprocess().await
-    };
+    let args: Vec<String> = env::args().collect();
+    let is_post = args.iter().any(|a| a == "--singlepage-post");
+    let derive_ids = args.iter().any(|a| a == "--derive-ids");
+    let result =
+        if let (Some("extract"), Some(what)) = (args.get(1).map(String::as_str), args.get(2)) {
+            // extract <what> writes out some of the data in the spec as JSON (or
+            // IDL), for other tools to consume. Most of it needs the preprocess
+            // phase to run first.
+            run_extract(what).await
+        } else if args.get(1).map(String::as_str) == Some("patch-ids") {
+            // patch-ids writes out a patch to the source which adds the IDs that
+            // --derive-ids would otherwise derive on every build.
+            run_patch_ids().await
+        } else if is_post {
+            // --singlepage-post runs the postprocess phase, which is currently only meant to be used on the
+            // singlepage output from Wattsi.
+            run_postprocess().await
+        } else {
+            // By default we run the preprocess phase, which creates a new input for Wattsi.
+            // With --derive-ids, examples and notes without IDs are given derived ones.
+            run_preprocess(derive_ids).await
+        };
 
     // This gives slightly prettier error-printing.
     if let Err(e) = result {
@@ -41,7 +51,118 @@ async fn main() -> io::Result<()> {
     Ok(())
 }
 
-async fn run_preprocess() -> io::Result<()> {
+async fn run_preprocess(derive_ids: bool) -> io::Result<()> {
+    // Because parsing can jump around the tree a little, it's most reasonable
+    // to just parse the whole document before doing any processing. Even for
+    // the HTML standard, this doesn't take too long.
+    let parsed = parser::parse_document_async(tokio::io::stdin()).await?;
+    preprocess(&parsed, derive_ids).await?;
+
+    // Finally, we write the result to standard out.
+    let serializable: SerializableHandle = parsed.document().clone().into();
+    serialize(
+        &mut BufWriter::with_capacity(128 * 1024, io::stdout()),
+        &serializable,
+        SerializeOpts::default(),
+    )?;
+    Ok(())
+}
+
+// Like run_preprocess, but instead of the document, writes out data extracted
+// from it, as JSON (or, for "idl", as an IDL file).
+async fn run_extract(what: &str) -> io::Result<()> {
+    let parsed = parser::parse_document_async(tokio::io::stdin()).await?;
+    // The IDL is extracted from the source as written, so that doing so needs
+    // no boilerplate files, and IDL in <!--COPY--> sources isn't seen twice.
+    if !matches!(what, "idl" | "exposure") {
+        preprocess(&parsed, false).await?;
+    }
+    let document = parsed.document().clone();
+
+    let mut out = BufWriter::with_capacity(128 * 1024, io::stdout());
+    match what {
+        "elements" => {
+            let mut elements = element_metadata::Processor::new();
+            dom_utils::scan_dom(&document, &mut |h| elements.visit(h));
+            serde_json::to_writer_pretty(&mut out, &elements.extract())?;
+        }
+        "attributes" => {
+            let mut attributes = attribute_metadata::Processor::new();
+            dom_utils::scan_dom(&document, &mut |h| attributes.visit(h));
+            serde_json::to_writer_pretty(&mut out, &attributes.extract())?;
+        }
+        "idl" => {
+            let mut idl = idl_extract::Processor::new(&parsed);
+            dom_utils::scan_dom(&document, &mut |h| idl.visit(h));
+            write!(out, "{}", idl.extract())?;
+        }
+        "exposure" => {
+            let mut interface_index = interface_index::Processor::new();
+            dom_utils::scan_dom(&document, &mut |h| interface_index.visit(h));
+            serde_json::to_writer_pretty(&mut out, &interface_index.exposure())?;
+        }
+        "tag-omission" => {
+            let mut tag_omission = tag_omission::Processor::new(&parsed);
+            dom_utils::scan_dom(&document, &mut |h| tag_omission.visit(h));
+            serde_json::to_writer_pretty(&mut out, &tag_omission.rules())?;
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Don't know how to extract {what:?}"),
+            ));
+        }
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+// Derives IDs for the elements which get self-links but have no ID, as
+// --derive-ids would, and writes out a patch to the source which adds them.
+async fn run_patch_ids() -> io::Result<()> {
+    let mut source = String::new();
+    tokio::io::stdin().read_to_string(&mut source).await?;
+    let parsed = parser::parse_document_async(source.as_bytes()).await?;
+    let document = parsed.document().clone();
+
+    let mut self_link = self_link::Processor::with_config(self_link_config_from_env(true)?);
+    dom_utils::scan_dom(&document, &mut |h| self_link.visit(h));
+
+    let insertions: Vec<id_patch::Insertion> = self_link
+        .derived_ids()
+        .into_iter()
+        .filter_map(|(node, id)| {
+            let NodeData::Element {
+                ref name,
+                ref attrs,
+                ..
+            } = node.data
+            else {
+                return None;
+            };
+            let class = attrs
+                .borrow()
+                .iter()
+                .find(|a| a.name.ns.is_empty() && &*a.name.local == "class")
+                .map_or_else(String::new, |a| a.value.to_string());
+            Some(id_patch::Insertion {
+                line: parsed.line_number_for(&node)?,
+                tag: name.local.to_string(),
+                class,
+                id,
+            })
+        })
+        .collect();
+    let (patch, warnings) = id_patch::patch("source", &source, &insertions);
+    for warning in warnings {
+        eprintln!("Warning: {warning}");
+    }
+    io::stdout().write_all(patch.as_bytes())?;
+    Ok(())
+}
+
+// Applies all of the preprocessing steps to the parsed document, in place.
+async fn preprocess(parsed: &RcDomWithLineNumbers, derive_ids: bool) -> io::Result<()> {
     // Since we're using Rc in the DOM implementation, we must ensure that tasks
     // which act on it are confined to this thread.
 
@@ -49,49 +170,86 @@ async fn run_preprocess() -> io::Result<()> {
     let cache_dir = path_from_env("HTML_CACHE", ".cache");
     let source_dir = path_from_env("HTML_SOURCE", "../html");
 
-    // Because parsing can jump around the tree a little, it's most reasonable
-    // to just parse the whole document before doing any processing. Even for
-    // the HTML standard, this doesn't take too long.
-    let parsed = parser::parse_document_async(tokio::io::stdin()).await?;
     let document = parsed.document().clone();
 
-    let mut boilerplate = boilerplate::Processor::new(cache_dir.clone(), source_dir.join("demos"));
-    let mut represents = represents::Processor::new();
-    let mut annotate_attributes = annotate_attributes::Processor::new();
-    let mut tag_omission = tag_omission::Processor::new();
+    let mut boilerplate = boilerplate::Processor::new(
+        parsed,
+        Arc::new(source_fs::DiskFs::new(cache_dir.clone())),
+        Arc::new(source_fs::DiskFs::new(source_dir.join("demos"))),
+    );
+    let mut example_conformance = example_conformance::Processor::new(parsed);
+    let mut element_index_check = element_index_check::Processor::new(parsed);
+    let mut represents = represents::Processor::new(parsed);
+    let mut transclusion = transclusion::Processor::new(parsed);
+    let mut element_index = element_index::Processor::new();
+    let mut annotate_attributes = annotate_attributes::Processor::new(parsed);
+    let mut event_handlers = event_handlers::Processor::new(parsed);
+    let mut reflection = reflection::Processor::new(parsed);
+    let mut tag_omission = tag_omission::Processor::new(parsed);
     let mut interface_index = interface_index::Processor::new();
-    let mut self_link = self_link::Processor::new();
-    let mut variables = variables::Processor::new(&parsed);
+    let mut idl_validation = idl_validation::Processor::new(parsed);
+    let mut element_interfaces = element_interfaces::Processor::new(parsed);
+    let mut self_link = self_link::Processor::with_config(self_link_config_from_env(derive_ids)?);
+    let mut variables = variables::Processor::new(parsed);
 
     // We do exactly one pass to identify the changes that need to be made.
     dom_utils::scan_dom(&document, &mut |h| {
         boilerplate.visit(h);
+        example_conformance.visit(h);
+        element_index_check.visit(h);
         represents.visit(h);
+        transclusion.visit(h);
+        element_index.visit(h);
         variables.visit(h);
         annotate_attributes.visit(h);
+        event_handlers.visit(h);
+        reflection.visit(h);
         tag_omission.visit(h);
         interface_index.visit(h);
+        idl_validation.visit(h);
+        element_interfaces.visit(h);
         self_link.visit(h);
     });
 
+    // Report anything suspicious that doesn't prevent building.
+    for warning in represents
+        .warnings()
+        .into_iter()
+        .chain(element_index_check.warnings())
+        .chain(tag_omission.warnings())
+        .chain(annotate_attributes.warnings())
+        .chain(event_handlers.warnings(interface_index.idl_blocks()))
+        .chain(reflection.warnings())
+        .chain(idl_validation.warnings(interface_index.idl_blocks()))
+        .chain(element_interfaces.warnings(interface_index.idl_blocks()))
+    {
+        eprintln!("Warning: {warning}");
+    }
+    for warning in example_conformance
+        .warnings()
+        .await?
+        .into_iter()
+        .chain(tag_omission.parser_discrepancies().await?)
+    {
+        eprintln!("Warning: {warning}");
+    }
+    for (node, id) in self_link.derived_ids() {
+        let line = line_prefix(parsed.line_number_for(&node));
+        eprintln!("Warning: {line}Derived ID {id} for an element with no ID.");
+    }
+
     // And then we apply all of the changes. These different processors mostly
     // apply quite local changes, so hopefully we never have to deal with
     // conflicts between them.
     boilerplate.apply().await?;
     represents.apply()?;
+    transclusion.apply()?;
+    element_index.apply()?;
     variables.apply()?;
     annotate_attributes.apply().await?;
     tag_omission.apply()?;
     interface_index.apply()?;
     self_link.apply()?;
-
-    // Finally, we write the result to standard out.
-    let serializable: SerializableHandle = document.into();
-    serialize(
-        &mut BufWriter::with_capacity(128 * 1024, io::stdout()),
-        &serializable,
-        SerializeOpts::default(),
-    )?;
     Ok(())
 }
 
@@ -127,3 +285,39 @@ where
         None => default.as_ref().into(),
     }
 }
+
+// Reads the self-link settings from the environment, so that specs other than
+// HTML can choose which elements get self-links and what they look like.
+fn self_link_config_from_env(derive_missing_ids: bool) -> io::Result<self_link::Config> {
+    let defaults = self_link::Config::default();
+    let base_url = match env::var("HTML_SELF_LINK_BASE_URL") {
+        Ok(url) => Url::parse(&url).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid HTML_SELF_LINK_BASE_URL {url:?}: {e}"),
+            )
+        })?,
+        Err(_) => defaults.base_url,
+    };
+    Ok(self_link::Config {
+        target_classes: env::var("HTML_SELF_LINK_TARGETS").map_or(defaults.target_classes, |v| {
+            v.split_whitespace().map(str::to_owned).collect()
+        }),
+        base_url,
+        link_class: env::var("HTML_SELF_LINK_CLASS").unwrap_or(defaults.link_class),
+        headings: flag_from_env("HTML_SELF_LINK_HEADINGS", defaults.headings),
+        heading_marker: env::var("HTML_SELF_LINK_HEADING_MARKER")
+            .unwrap_or(defaults.heading_marker),
+        dfns: flag_from_env("HTML_SELF_LINK_DFNS", defaults.dfns),
+        domintro: flag_from_env("HTML_SELF_LINK_DOMINTRO", defaults.domintro),
+        derive_missing_ids,
+    })
+}
+
+// Treats any value other than "" or "0" as turning the setting on.
+fn flag_from_env(var: &str, default: bool) -> bool {
+    match env::var_os(var) {
+        Some(v) => !v.is_empty() && v != "0",
+        None => default,
+    }
+}
@@ -0,0 +1,437 @@
+//! A model of the rules in the "Optional tags" section, each of which has the
+//! form:
+//!   <p>An <code>li</code> element's end tag may be omitted if the <code>li</code>
+//!   element is immediately followed by another <code>li</code> element or if
+//!   there is no more content in the parent element.</p>
+//!
+//! Each rule is split into alternatives ("or if"), each of which is a list of
+//! conditions which must all hold ("and if"). Conditions are classified where
+//! their wording is recognized, and keep their original markup so that the
+//! prose can be regenerated.
+
+use std::sync::LazyLock;
+
+use html5ever::local_name;
+use html5ever::tendril::StrTendril;
+use markup5ever_rcdom::{Handle, NodeData};
+use regex::Regex;
+use serde::Serialize;
+
+use crate::dom_utils::{self, NodeHandleExt};
+
+static FOLLOWED_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^the (\S+ )?element is immediately followed by").unwrap());
+static NOT_FOLLOWED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^the (?:\S+ )?element is not immediately followed by (.+)$").unwrap()
+});
+static NOT_PRECEDED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^the (?:\S+ )?element is not immediately preceded by (.+)$").unwrap()
+});
+static FIRST_THING_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^the first thing inside the (?:\S+ )?element is (not )?(.+)$").unwrap()
+});
+
+/// The text between the <code> and the first condition.
+static LEAD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*element(?:'|’)s\s+(start|end)\s+tag\s+(?:may|can)\s+be\s+omitted\s+if\s*")
+        .unwrap()
+});
+
+/// What separates conditions, capturing whether they are alternatives, or
+/// ends them.
+static SEPARATOR_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r",?\s+(or|and)\s+if\s+|\.(?:\s|$)").unwrap());
+
+static MAY_RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\bmay(\s+be\s+omitted)").unwrap());
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tag {
+    Start,
+    End,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ConditionKind {
+    /// "the li element is immediately followed by another li element"
+    FollowedBy { elements: Vec<String> },
+
+    /// "the html element is not immediately followed by a comment"
+    NotFollowedBy { what: String },
+
+    /// "the element is not immediately preceded by another colgroup element
+    /// whose end tag has been omitted"
+    NotPrecededBy { what: String },
+
+    /// "there is no more content in the parent element", optionally "and the
+    /// parent element is an HTML element that is not an a, ... element"
+    NoMoreContentInParent { except_parents: Vec<String> },
+
+    /// "the first thing inside the head element is an element"
+    FirstThingInside { negated: bool, what: String },
+
+    /// "the element is empty"
+    Empty,
+
+    /// Anything which isn't recognized.
+    Other,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Condition {
+    #[serde(flatten)]
+    pub kind: ConditionKind,
+
+    /// The condition as plain text.
+    pub text: String,
+
+    /// The text which joined this to the previous condition, e.g., ", and if ".
+    #[serde(skip)]
+    separator: String,
+
+    /// The original markup of the condition.
+    #[serde(skip)]
+    nodes: Vec<Handle>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Alternative {
+    /// The text which joined this to the previous alternative, e.g., " or if ".
+    #[serde(skip)]
+    separator: String,
+
+    pub all_of: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct OmissionRule {
+    pub element: String,
+    pub tag: Tag,
+
+    /// The tag may be omitted if all the conditions of any alternative hold.
+    pub any_of: Vec<Alternative>,
+
+    /// Any text after the rule, such as "(It can't be omitted if the element is
+    /// empty.)".
+    pub note: Option<String>,
+
+    /// The article ("A" or "An") and <code> which begin the paragraph.
+    #[serde(skip)]
+    prefix: Vec<Handle>,
+
+    /// The text between the <code> and the first condition, as written, e.g.,
+    /// " element’s end tag may be omitted if ".
+    #[serde(skip)]
+    lead: String,
+
+    /// Whether the conditions were ended by a period.
+    #[serde(skip)]
+    period: bool,
+
+    /// The original markup of the note.
+    #[serde(skip)]
+    note_nodes: Vec<Handle>,
+}
+
+fn normalized_text(nodes: &[Handle]) -> String {
+    let text: String = nodes.iter().map(|n| n.text_content().to_string()).collect();
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn code_texts(nodes: &[Handle]) -> Vec<String> {
+    let mut codes = Vec::new();
+    for node in nodes {
+        dom_utils::scan_dom(node, &mut |n| {
+            if n.is_html_element(&local_name!("code")) {
+                codes.push(n.text_content().to_string());
+            }
+        });
+    }
+    codes
+}
+
+fn push_text(nodes: &mut Vec<Handle>, text: &str) {
+    if !text.is_empty() {
+        nodes.push(Handle::create_text_node(text));
+    }
+}
+
+impl Condition {
+    fn new(separator: String, nodes: Vec<Handle>) -> Self {
+        let text = normalized_text(&nodes);
+        let kind = if let Some(c) = FOLLOWED_RE.captures(&text) {
+            // Skip the element itself, if it was named.
+            let skip = if c.get(1).is_some() { 1 } else { 0 };
+            ConditionKind::FollowedBy {
+                elements: code_texts(&nodes).into_iter().skip(skip).collect(),
+            }
+        } else if let Some(c) = NOT_FOLLOWED_RE.captures(&text) {
+            ConditionKind::NotFollowedBy {
+                what: c[1].to_owned(),
+            }
+        } else if let Some(c) = NOT_PRECEDED_RE.captures(&text) {
+            ConditionKind::NotPrecededBy {
+                what: c[1].to_owned(),
+            }
+        } else if text.starts_with("there is no more content in the parent element") {
+            ConditionKind::NoMoreContentInParent {
+                except_parents: code_texts(&nodes),
+            }
+        } else if let Some(c) = FIRST_THING_RE.captures(&text) {
+            ConditionKind::FirstThingInside {
+                negated: c.get(1).is_some(),
+                what: c[2].to_owned(),
+            }
+        } else if text == "the element is empty" {
+            ConditionKind::Empty
+        } else {
+            ConditionKind::Other
+        };
+        Condition {
+            kind,
+            text,
+            separator,
+            nodes,
+        }
+    }
+}
+
+impl OmissionRule {
+    /// Parses a paragraph from the "Optional tags" section, or returns None if
+    /// it does not have the expected form.
+    pub fn parse(paragraph: &Handle) -> Option<Self> {
+        let children = paragraph.children.borrow();
+        let (article, code, lead) = match &children[..] {
+            [a, b, c, ..]
+                if a.node_text()
+                    .is_some_and(|t| t.trim() == "A" || t.trim() == "An")
+                    && b.is_html_element(&local_name!("code")) =>
+            {
+                (a, b, c.node_text()?)
+            }
+            _ => return None,
+        };
+        let lead_match = LEAD_RE.captures(&lead)?;
+        let tag = if &lead_match[1] == "start" {
+            Tag::Start
+        } else {
+            Tag::End
+        };
+
+        let mut rule = OmissionRule {
+            element: code.text_content().to_string(),
+            tag,
+            any_of: Vec::new(),
+            note: None,
+            prefix: vec![article.clone(), code.clone()],
+            lead: lead_match[0].to_owned(),
+            period: false,
+            note_nodes: Vec::new(),
+        };
+
+        // The text after the lead-in, and the rest of the paragraph.
+        let rest_of_lead = StrTendril::from(&lead[lead_match.get(0).unwrap().end()..]);
+        let pieces = std::iter::once(Handle::create_text_node(rest_of_lead))
+            .chain(children[3..].iter().cloned());
+
+        let mut alternative = Alternative {
+            separator: String::new(),
+            all_of: Vec::new(),
+        };
+        let mut condition_separator = String::new();
+        let mut nodes = Vec::new();
+        let mut done = false;
+        for piece in pieces {
+            let text = match piece.data {
+                NodeData::Text { ref contents } => contents.borrow().to_string(),
+                _ => {
+                    if done {
+                        rule.note_nodes.push(piece);
+                    } else {
+                        nodes.push(piece);
+                    }
+                    continue;
+                }
+            };
+            let mut rest = &text[..];
+            while !done && let Some(c) = SEPARATOR_RE.captures(rest) {
+                let m = c.get(0).unwrap();
+                push_text(&mut nodes, &rest[..m.start()]);
+                let condition = Condition::new(
+                    std::mem::take(&mut condition_separator),
+                    std::mem::take(&mut nodes),
+                );
+                alternative.all_of.push(condition);
+                let separator = m.as_str();
+                if separator.starts_with('.') {
+                    done = true;
+                    rule.period = true;
+                    rest = &rest[m.start() + 1..];
+                    break;
+                } else if c.get(1).is_some_and(|w| w.as_str() == "or") {
+                    let finished = std::mem::replace(
+                        &mut alternative,
+                        Alternative {
+                            separator: separator.to_owned(),
+                            all_of: Vec::new(),
+                        },
+                    );
+                    rule.any_of.push(finished);
+                } else {
+                    condition_separator = separator.to_owned();
+                }
+                rest = &rest[m.end()..];
+            }
+            if done {
+                push_text(&mut rule.note_nodes, rest);
+            } else {
+                push_text(&mut nodes, rest);
+            }
+        }
+        if !done {
+            alternative
+                .all_of
+                .push(Condition::new(condition_separator, nodes));
+        }
+        rule.any_of.push(alternative);
+
+        let note = normalized_text(&rule.note_nodes);
+        if !note.is_empty() {
+            rule.note = Some(note);
+        }
+        Some(rule)
+    }
+
+    /// Regenerates the prose of the rule, in the form used within element
+    /// definitions (i.e., "can" rather than "may").
+    pub fn to_nodes(&self) -> Vec<Handle> {
+        let mut nodes: Vec<Handle> = self.prefix.iter().map(|n| n.deep_clone()).collect();
+        nodes.push(Handle::create_text_node(
+            MAY_RE.replace(&self.lead, "can$1").as_ref(),
+        ));
+        for alternative in &self.any_of {
+            push_text(&mut nodes, &alternative.separator);
+            for condition in &alternative.all_of {
+                push_text(&mut nodes, &condition.separator);
+                nodes.extend(condition.nodes.iter().map(|n| n.deep_clone()));
+            }
+        }
+        if self.period {
+            nodes.push(Handle::create_text_node("."));
+        }
+        nodes.extend(self.note_nodes.iter().map(|n| n.deep_clone()));
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_document_async, tests::serialize_for_test};
+    use crate::rcdom_with_line_numbers::RcDomWithLineNumbers;
+    use std::io;
+
+    #[allow(clippy::type_complexity)]
+    async fn parse_rules(
+        source: &str,
+    ) -> io::Result<(RcDomWithLineNumbers, Vec<(Handle, Option<OmissionRule>)>)> {
+        let parsed = parse_document_async(source.as_bytes()).await?;
+        let mut rules = Vec::new();
+        dom_utils::scan_dom(parsed.document(), &mut |n| {
+            if n.is_html_element(&local_name!("p")) {
+                rules.push((n.clone(), OmissionRule::parse(n)));
+            }
+        });
+        // The rules refer to nodes in the document, so it is returned too, to
+        // keep them alive.
+        Ok((parsed, rules))
+    }
+
+    #[tokio::test]
+    async fn test_parse_and_regenerate() -> io::Result<()> {
+        let (_parsed, rules) = parse_rules(
+            r#"<!DOCTYPE html>
+<p>An <code>li</code> element's end tag may be omitted if the <code>li</code> element is immediately followed by another <code>li</code> element or if there is no more content in the parent element.</p>
+<p>A <code>colgroup</code> element's start tag may be omitted if the first thing inside the <code>colgroup</code> element is a <code>col</code> element, and if the element is not immediately preceded by another <code>colgroup</code> element whose end tag has been omitted. (It can't be omitted if the element is empty.)</p>
+<p>A <code>head</code> element's start tag may be omitted if the element is empty, or if the first thing inside the <code>head</code> element is an element.</p>
+<p>An <code>audio</code> element is quite audible.</p>
+<p>An <code>rt</code> element’s end tag may be omitted if the <code>rt</code> element is immediately
+followed by an <code>rt</code> element, or
+if there is no more content in the parent element</p>"#,
+        )
+        .await?;
+
+        let (_, li) = &rules[0];
+        let li = li.as_ref().unwrap();
+        assert_eq!(li.element, "li");
+        assert_eq!(li.tag, Tag::End);
+        assert_eq!(li.any_of.len(), 2);
+        assert_eq!(
+            li.any_of[0].all_of[0].kind,
+            ConditionKind::FollowedBy {
+                elements: vec!["li".to_owned()]
+            }
+        );
+        assert_eq!(
+            li.any_of[1].all_of[0].kind,
+            ConditionKind::NoMoreContentInParent {
+                except_parents: vec![]
+            }
+        );
+        assert_eq!(li.note, None);
+
+        let (_, colgroup) = &rules[1];
+        let colgroup = colgroup.as_ref().unwrap();
+        assert_eq!(colgroup.tag, Tag::Start);
+        assert_eq!(colgroup.any_of.len(), 1);
+        let kinds: Vec<_> = colgroup.any_of[0].all_of.iter().map(|c| &c.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                &ConditionKind::FirstThingInside {
+                    negated: false,
+                    what: "a col element".to_owned()
+                },
+                &ConditionKind::NotPrecededBy {
+                    what: "another colgroup element whose end tag has been omitted".to_owned()
+                },
+            ]
+        );
+        assert_eq!(
+            colgroup.note.as_deref(),
+            Some("(It can't be omitted if the element is empty.)")
+        );
+
+        let (_, head) = &rules[2];
+        let head = head.as_ref().unwrap();
+        assert_eq!(head.any_of[0].all_of[0].kind, ConditionKind::Empty);
+
+        assert!(rules[3].1.is_none());
+
+        // Conditions may be separated across lines, and the rule needn't end
+        // with a period.
+        let (_, rt) = &rules[4];
+        let rt = rt.as_ref().unwrap();
+        assert_eq!(rt.any_of.len(), 2);
+        assert_eq!(
+            rt.any_of[0].all_of[0].kind,
+            ConditionKind::FollowedBy {
+                elements: vec!["rt".to_owned()]
+            }
+        );
+
+        // Regenerating gives back the original, except that "may" becomes "can".
+        for (p, rule) in rules.iter().filter(|(_, rule)| rule.is_some()) {
+            let regenerated = Handle::create_element(local_name!("p"))
+                .children(rule.as_ref().unwrap().to_nodes().into_iter())
+                .build();
+            assert_eq!(
+                serialize_for_test(&[regenerated]),
+                serialize_for_test(std::slice::from_ref(p))
+                    .replace("may be omitted", "can be omitted")
+            );
+        }
+        Ok(())
+    }
+}
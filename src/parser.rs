@@ -46,15 +46,7 @@ pub async fn parse_fragment_async<R: AsyncRead + Unpin>(
     r: R,
     context: &Handle,
 ) -> io::Result<Vec<Handle>> {
-    let parser = driver::parse_fragment_for_element(
-        RcDomWithLineNumbers::default(),
-        create_error_opts(),
-        context.clone(),
-        false,
-        None,
-    );
-
-    let dom = parse_internal_async(parser, r).await?;
+    let dom = parse_fragment_unchecked_async(r, context).await?;
     dom.create_error_from_parse_errors()?;
 
     let document = dom.document();
@@ -65,14 +57,38 @@ pub async fn parse_fragment_async<R: AsyncRead + Unpin>(
     Ok(new_children)
 }
 
+/// Like parse_fragment_async, but leaves it to the caller to inspect any parse
+/// errors. The fragment is found in the children of the document's root element.
+pub async fn parse_fragment_unchecked_async<R: AsyncRead + Unpin>(
+    r: R,
+    context: &Handle,
+) -> io::Result<RcDomWithLineNumbers> {
+    let parser = driver::parse_fragment_for_element(
+        RcDomWithLineNumbers::default(),
+        create_error_opts(),
+        context.clone(),
+        false,
+        None,
+    );
+    parse_internal_async(parser, r).await
+}
+
 pub async fn parse_document_async<R: AsyncRead + Unpin>(r: R) -> io::Result<RcDomWithLineNumbers> {
-    let parser = driver::parse_document(RcDomWithLineNumbers::default(), create_error_opts());
-    let dom = parse_internal_async(parser, r).await?;
+    let dom = parse_document_unchecked_async(r).await?;
     dom.create_error_from_parse_errors()?;
 
     Ok(dom)
 }
 
+/// Like parse_document_async, but leaves it to the caller to inspect any parse
+/// errors.
+pub async fn parse_document_unchecked_async<R: AsyncRead + Unpin>(
+    r: R,
+) -> io::Result<RcDomWithLineNumbers> {
+    let parser = driver::parse_document(RcDomWithLineNumbers::default(), create_error_opts());
+    parse_internal_async(parser, r).await
+}
+
 fn create_error_opts() -> ParseOpts {
     ParseOpts {
         tokenizer: TokenizerOpts {
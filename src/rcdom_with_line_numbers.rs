@@ -18,6 +18,7 @@ pub struct RcDomWithLineNumbers {
     dom: RcDom,
     current_line: Cell<u64>,
     node_line_map: RefCell<HashMap<*const Node, u64>>,
+    located_errors: RefCell<Vec<(u64, Cow<'static, str>)>>,
 }
 
 #[cfg(test)]
@@ -33,16 +34,29 @@ impl RcDomWithLineNumbers {
         &self.dom.document
     }
 
+    /// Returns the parse errors encountered, each prefixed with its line number.
+    pub fn parse_error_messages(&self) -> Vec<String> {
+        self.dom
+            .errors
+            .borrow()
+            .iter()
+            .map(|e| e.to_string())
+            .collect()
+    }
+
+    /// Returns the parse errors encountered, with their line numbers separate
+    /// from the message.
+    pub fn located_parse_errors(&self) -> Vec<(u64, String)> {
+        self.located_errors
+            .borrow()
+            .iter()
+            .map(|(line, msg)| (*line, msg.to_string()))
+            .collect()
+    }
+
     pub fn create_error_from_parse_errors(&self) -> io::Result<()> {
         if !self.dom.errors.borrow().is_empty() {
-            let error_messages = self
-                .dom
-                .errors
-                .borrow()
-                .iter()
-                .map(|e| e.to_string())
-                .collect::<Vec<String>>()
-                .join("\n");
+            let error_messages = self.parse_error_messages().join("\n");
             Err(io::Error::new(
                 io::ErrorKind::InvalidData,
                 format!("Parse errors encountered:\n\n{error_messages}"),
@@ -52,20 +66,36 @@ impl RcDomWithLineNumbers {
         }
     }
 
-    /// Returns the 1-based line number where the element represented by `handle`
-    /// was created, if known.
+    /// Returns the 1-based line number where the element or comment represented
+    /// by `handle` was created, if known.
     pub fn line_number_for(&self, handle: &Handle) -> Option<u64> {
         let key = Rc::as_ptr(handle);
         self.node_line_map.borrow().get(&key).cloned()
     }
 }
 
+/// Returns "Line N: ", for prefixing a message about line N, or nothing if the
+/// line isn't known.
+pub fn line_prefix(line: Option<u64>) -> String {
+    line.map(|ln| format!("Line {ln}: ")).unwrap_or_default()
+}
+
+/// Sorts warnings by line, and prefixes each with its line, if known.
+pub fn format_warnings(mut warnings: Vec<(Option<u64>, String)>) -> Vec<String> {
+    warnings.sort();
+    warnings
+        .into_iter()
+        .map(|(line, message)| format!("{}{message}", line_prefix(line)))
+        .collect()
+}
+
 impl Default for RcDomWithLineNumbers {
     fn default() -> Self {
         Self {
             dom: RcDom::default(),
             current_line: Cell::new(1),
             node_line_map: RefCell::new(HashMap::new()),
+            located_errors: RefCell::new(Vec::new()),
         }
     }
 }
@@ -79,6 +109,9 @@ impl TreeSink for RcDomWithLineNumbers {
     // Override the parse_error method to add line numbers to the error messages.
     fn parse_error(&self, msg: Cow<'static, str>) {
         let msg_with_line = format!("Line {}: {}", self.current_line.get(), msg);
+        self.located_errors
+            .borrow_mut()
+            .push((self.current_line.get(), msg));
         self.dom.parse_error(Cow::Owned(msg_with_line));
     }
 
@@ -106,6 +139,15 @@ impl TreeSink for RcDomWithLineNumbers {
         h
     }
 
+    // Override to record the current line number for each created comment, too.
+    fn create_comment(&self, text: StrTendril) -> Self::Handle {
+        let h = self.dom.create_comment(text);
+        let key = Rc::as_ptr(&h);
+        let line = self.current_line.get();
+        self.node_line_map.borrow_mut().insert(key, line);
+        h
+    }
+
     // Delegate all other methods to RcDom.
     delegate! {
         to self.dom {
@@ -113,8 +155,6 @@ impl TreeSink for RcDomWithLineNumbers {
 
             fn elem_name<'a>(&'a self, target: &'a Self::Handle) -> ExpandedName<'a>;
 
-            fn create_comment(&self, text: StrTendril) -> Self::Handle;
-
             fn create_pi(&self, target: StrTendril, data: StrTendril) -> Self::Handle;
 
             fn append(&self, parent: &Self::Handle, child: NodeOrText<Self::Handle>);
@@ -0,0 +1,234 @@
+//! Checks that the IDL attributes which each element's interface marks with
+//! [Reflect] (or a variant such as [ReflectURL]) correspond to content
+//! attributes of the element, and that the element's content attributes which
+//! have an IDL attribute of the same name are marked as reflected.
+
+use std::collections::{BTreeMap, HashMap};
+
+use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::Handle;
+
+use crate::dom_utils::{self, NodeHandleExt};
+use crate::interface_index::is_idl_block;
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, format_warnings};
+use crate::webidl::{self, ExtendedAttribute, MemberKind};
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// Finds the <dl class="element"> for each element.
+    element_dls: dom_utils::ElementDlTracker,
+
+    /// The <dl class="element"> blocks, with the elements they describe.
+    dls: Vec<(Vec<StrTendril>, Handle)>,
+}
+
+/// An IDL attribute of an element's interface.
+struct IdlAttribute {
+    name: String,
+
+    /// The content attribute it reflects, if it is marked [Reflect].
+    reflects: Option<String>,
+    line: Option<u64>,
+}
+
+fn data_x() -> QualName {
+    QualName::new(None, ns!(), LocalName::from("data-x"))
+}
+
+/// Returns the <dd>s for the <dt> with the given key.
+fn descriptions(dl: &Handle, key: &str) -> Vec<Handle> {
+    dl.children
+        .borrow()
+        .iter()
+        .find(|c| {
+            c.is_html_element(&local_name!("dt")) && c.any_child(|s| s.attribute_is(&data_x(), key))
+        })
+        .map(dom_utils::dt_descriptions)
+        .unwrap_or_default()
+}
+
+/// Variants of [Reflect] whose values are not the name of the content
+/// attribute.
+const REFLECT_MODIFIERS: &[&str] = &["ReflectDefault", "ReflectRange"];
+
+/// Returns the content attribute reflected by an IDL attribute with the given
+/// extended attributes, if any is [Reflect] or one of its variants. This is
+/// the value of [Reflect=name] (or e.g. [ReflectURL=name]), or else the IDL
+/// attribute's name in lowercase.
+fn reflected_name(extended_attributes: &[ExtendedAttribute], idl_name: &str) -> Option<String> {
+    let mut reflects = extended_attributes
+        .iter()
+        .filter(|ea| ea.name.starts_with("Reflect"))
+        .peekable();
+    reflects.peek()?;
+    let name = reflects
+        .filter(|ea| !REFLECT_MODIFIERS.contains(&&ea.name[..]))
+        .find_map(|ea| match &ea.values[..] {
+            [name] => Some(name.clone()),
+            _ => None,
+        });
+    Some(name.unwrap_or_else(|| idl_name.to_lowercase()))
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            element_dls: Default::default(),
+            dls: Vec::new(),
+        }
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        if let Some(elements) = self.element_dls.visit(node) {
+            self.dls.push((elements, node.clone()));
+        }
+    }
+
+    /// Returns the attributes declared by the IDL blocks in the <dd>s. Blocks
+    /// which don't parse are skipped, since idl_validation reports them.
+    fn idl_attributes(&self, dds: &[Handle]) -> Vec<IdlAttribute> {
+        let mut attributes = Vec::new();
+        for dd in dds {
+            dom_utils::scan_dom(dd, &mut |code| {
+                if !is_idl_block(code) {
+                    return;
+                }
+                let Ok(definitions) = webidl::parse(&code.text_content()) else {
+                    return;
+                };
+                let start_line = self.parsed.line_number_for(code);
+                for member in definitions.iter().flat_map(|d| d.members.iter()) {
+                    let (MemberKind::Attribute, Some(name)) = (member.kind, &member.name) else {
+                        continue;
+                    };
+                    attributes.push(IdlAttribute {
+                        name: name.clone(),
+                        reflects: reflected_name(&member.extended_attributes, name),
+                        line: start_line.map(|ln| ln + member.line - 1),
+                    });
+                }
+            });
+        }
+        attributes
+    }
+
+    /// Returns a description of each mismatch between reflected IDL
+    /// attributes and content attributes. Should be called after visiting the
+    /// document.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<(Option<u64>, String)> = Vec::new();
+        for (elements, dl) in self.dls.iter() {
+            let element = &elements[0];
+
+            // Map from content attribute name to the <dd> which lists it.
+            let mut content_attributes: BTreeMap<String, Handle> = BTreeMap::new();
+            for dd in descriptions(dl, "concept-element-attributes") {
+                let mut name = None;
+                dom_utils::scan_dom(&dd, &mut |n| {
+                    if name.is_none()
+                        && n.is_html_element(&local_name!("code"))
+                        && n.get_attribute(&data_x())
+                            .is_some_and(|d| d.starts_with("attr-"))
+                    {
+                        name = Some(n.text_content().trim().to_owned());
+                    }
+                });
+                if let Some(name) = name {
+                    content_attributes.entry(name).or_insert(dd);
+                }
+            }
+
+            let idl_attributes = self.idl_attributes(&descriptions(dl, "concept-element-dom"));
+            let mut reflected: HashMap<&str, &IdlAttribute> = HashMap::new();
+            for attribute in idl_attributes.iter() {
+                let Some(ref content_attribute) = attribute.reflects else {
+                    continue;
+                };
+                reflected.insert(content_attribute, attribute);
+                if !content_attributes.contains_key(content_attribute) {
+                    warnings.push((
+                        attribute.line,
+                        format!(
+                            "IDL attribute {} of <{element}> reflects the {content_attribute} content attribute, which is not among the element's content attributes.",
+                            attribute.name
+                        ),
+                    ));
+                }
+            }
+            for (name, dd) in content_attributes.iter() {
+                if reflected.contains_key(&name[..]) {
+                    continue;
+                }
+                if let Some(attribute) = idl_attributes
+                    .iter()
+                    .find(|a| a.reflects.is_none() && a.name.to_lowercase() == *name)
+                {
+                    warnings.push((
+                        self.parsed.line_number_for(dd),
+                        format!(
+                            "Content attribute {name} of <{element}> has an IDL attribute {} which is not marked [Reflect].",
+                            attribute.name
+                        ),
+                    ));
+                }
+            }
+        }
+        format_warnings(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_document_async;
+    use std::io;
+
+    #[tokio::test]
+    async fn test_warnings() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<h4>The <dfn element><code>label</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-attributes">Content attributes</span>:</dt>
+<dd><span>Global attributes</span></dd>
+<dd><code data-x="attr-label-for">for</code></dd>
+<dd><code data-x="attr-label-kind">kind</code></dd>
+<dd><code data-x="attr-label-size">size</code></dd>
+<dd><code data-x="attr-label-src">src</code></dd>
+<dd><code data-x="attr-label-width">width</code></dd>
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd><pre><code class="idl">interface <dfn interface>HTMLLabelElement</dfn> : <span>HTMLElement</span> {
+  [<span>CEReactions</span>, <span>Reflect</span>=for] attribute DOMString <span data-x="dom-label-htmlFor">htmlFor</span>;
+  [<span>CEReactions</span>] attribute DOMString <span data-x="dom-label-kind">kind</span>;
+  [<span>CEReactions</span>, <span>Reflect</span>, <span>ReflectRange</span>=(1, 100)] attribute unsigned long <span data-x="dom-label-size">size</span>;
+  [<span>CEReactions</span>, <span>ReflectURL</span>] attribute USVString <span data-x="dom-label-src">src</span>;
+  [<span>CEReactions</span>, <span>ReflectNonNegative</span>=width] attribute long <span data-x="dom-label-displayWidth">displayWidth</span>;
+  [<span>CEReactions</span>, <span>Reflect</span>] attribute boolean <span data-x="dom-label-noWrap">noWrap</span>;
+  readonly attribute <span>HTMLFormElement</span>? <span data-x="dom-label-form">form</span>;
+};</code></pre></dd>
+</dl>
+<h4>The <dfn element><code>span</code></dfn> element</h4>
+<dl class="element">
+<dt><span data-x="concept-element-dom">DOM interface</span>:</dt>
+<dd>Uses <code>HTMLSpanElement</code>.</dd>
+</dl>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.warnings(),
+            vec![
+                "Line 7: Content attribute kind of <label> has an IDL attribute kind which is not marked [Reflect].",
+                "Line 18: IDL attribute noWrap of <label> reflects the nowrap content attribute, which is not among the element's content attributes.",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -1,21 +1,36 @@
 //! Replaces <!--REPRESENTS tagname--> comments with the HTML which appears in a
 //! paragraph of the form:
 //!   <p>The <code>tagname</code> element <span>represents</span> ...</p>
+//!
+//! It also reports elements defined with <dfn element> which lack such a
+//! paragraph, or whose paragraph is never used by a placeholder, and tag names
+//! with more than one paragraph (in which case the last one is used).
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::io;
 use std::rc::Rc;
 
-use crate::dom_utils::NodeHandleExt;
-use html5ever::local_name;
+use crate::dom_utils::{NodeHandleExt, capitalize};
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, line_prefix};
 use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
 use markup5ever_rcdom::{Handle, NodeData};
 
-pub struct Processor {
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
     /// Map from tag name (as found in the paragraph) to the <span> which
     /// contains the text "represents".
     represents: HashMap<StrTendril, Handle>,
 
+    /// "represents" paragraphs for tag names which already had one, with the
+    /// <span> of the previous one.
+    duplicates: Vec<(StrTendril, Handle, Handle)>,
+
+    /// Elements defined with <dfn element>, in document order.
+    element_dfns: Vec<(StrTendril, Handle)>,
+
     /// List of <!--REPRESENTS--> comments to be replaced, and what tag name
     /// they correspond to.
     placeholders: Vec<(Handle, StrTendril)>,
@@ -24,7 +39,7 @@ pub struct Processor {
 /// Walks from the text node "represents" and finds the tag name and the
 /// span that marks where the description begins, or returns None if that
 /// cannot be found.
-fn find_tag_name(represents_text: &Handle) -> Option<(StrTendril, Handle)> {
+pub fn find_tag_name(represents_text: &Handle) -> Option<(StrTendril, Handle)> {
     let span = represents_text
         .parent_node()
         .filter(|p| p.is_html_element(&local_name!("span")))?;
@@ -45,23 +60,42 @@ fn find_tag_name(represents_text: &Handle) -> Option<(StrTendril, Handle)> {
     }
 }
 
-impl Processor {
-    pub fn new() -> Self {
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
         Self {
+            parsed,
             represents: HashMap::new(),
+            duplicates: Vec::new(),
+            element_dfns: Vec::new(),
             placeholders: Vec::new(),
         }
     }
 
+    fn line_prefix(&self, node: &Handle) -> String {
+        line_prefix(self.parsed.line_number_for(node))
+    }
+
     /// Should be called for each node the document. Records when it sees a
     /// <span>represents</span> and which element it is defining
     pub fn visit(&mut self, node: &Handle) {
         match node.data {
             NodeData::Text { ref contents } if contents.borrow().as_ref() == "represents" => {
-                if let Some((tag, span)) = find_tag_name(node) {
-                    self.represents.insert(tag, span);
+                if let Some((tag, span)) = find_tag_name(node)
+                    && let Some(previous) = self.represents.insert(tag.clone(), span.clone())
+                {
+                    self.duplicates.push((tag, span, previous));
                 }
             }
+            NodeData::Element { .. }
+                if node.is_html_element(&local_name!("dfn"))
+                    && node.has_attribute(&QualName::new(
+                        None,
+                        ns!(),
+                        LocalName::from("element"),
+                    )) =>
+            {
+                self.element_dfns.push((node.text_content(), node.clone()));
+            }
             NodeData::Comment { ref contents } if contents.starts_with("REPRESENTS ") => {
                 self.placeholders
                     .push((node.clone(), contents.subtendril(11, contents.len32() - 11)));
@@ -70,14 +104,55 @@ impl Processor {
         }
     }
 
+    /// Returns elements which have no "represents" paragraph, or whose
+    /// paragraph is never used by a <!--REPRESENTS--> placeholder, and
+    /// duplicate paragraphs. Should be called after visiting the document and
+    /// before `apply`.
+    pub fn warnings(&self) -> Vec<String> {
+        let used: HashSet<&str> = self.placeholders.iter().map(|(_, tag)| &tag[..]).collect();
+        let mut seen: HashSet<&str> = HashSet::new();
+        let mut warnings = Vec::new();
+        for (tag, dfn) in self.element_dfns.iter() {
+            if !seen.insert(tag) {
+                continue;
+            }
+            match self.represents.get(tag) {
+                None => warnings.push(format!(
+                    "{}The <{tag}> element has no \"represents\" paragraph.",
+                    self.line_prefix(dfn)
+                )),
+                Some(span) if !used.contains(&tag[..]) => warnings.push(format!(
+                    "{}The \"represents\" paragraph for <{tag}> is never used by a <!--REPRESENTS--> placeholder.",
+                    self.line_prefix(span)
+                )),
+                Some(_) => (),
+            }
+        }
+        for (tag, span, previous) in self.duplicates.iter() {
+            let previous = self
+                .parsed
+                .line_number_for(previous)
+                .map(|ln| format!(" (previous one on line {ln})"))
+                .unwrap_or_default();
+            warnings.push(format!(
+                "{}Duplicate \"represents\" paragraph for <{tag}>{previous}.",
+                self.line_prefix(span)
+            ));
+        }
+        warnings
+    }
+
     pub fn apply(self) -> io::Result<()> {
-        for (placeholder, ref tag) in self.placeholders {
+        for (placeholder, tag) in self.placeholders.iter() {
             let span = match self.represents.get(tag) {
                 Some(span) => span,
                 None => {
                     return Err(io::Error::new(
                         io::ErrorKind::InvalidData,
-                        format!("<!--REPRESENTS {tag}--> refers to unknown tag"),
+                        format!(
+                            "{}<!--REPRESENTS {tag}--> refers to unknown tag",
+                            self.line_prefix(placeholder)
+                        ),
                     ));
                 }
             };
@@ -107,18 +182,6 @@ impl Processor {
     }
 }
 
-fn capitalize(text: &str) -> StrTendril {
-    let mut chars = text.chars();
-    match chars.next() {
-        Some(c) => {
-            let mut capitalized = StrTendril::from_char(c.to_ascii_uppercase());
-            capitalized.push_slice(chars.as_str());
-            capitalized
-        }
-        None => StrTendril::new(),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -130,7 +193,7 @@ mod tests {
         // Uses can occur either before or after.
         let parsed = parse_document_async("<!DOCTYPE html><p><!--REPRESENTS chair--><p>The <code>chair</code> element <span>represents</span> a seat\nat a <code>table</code>.<p><!--REPRESENTS chair-->".as_bytes()).await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply()?;
         assert_eq!(
@@ -145,10 +208,64 @@ mod tests {
         // Uses can occur either before or after.
         let parsed = parse_document_async("<!DOCTYPE html><p><!--REPRESENTS chain--><p>The <code>chair</code> element <span>represents</span> a seat\nat a <code>table</code>.<p><!--REPRESENTS chair-->".as_bytes()).await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         let result = proc.apply();
         assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidData));
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_represents_completeness() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<h4>The <dfn element>chair</dfn> element</h4>
+<p>The <code>chair</code> element <span>represents</span> a seat.
+<h4>The <dfn element>table</dfn> element</h4>
+<p>The <code>table</code> element <span>represents</span> a surface.
+<h4>The <dfn element>stool</dfn> element</h4>
+<p><!--REPRESENTS chair-->"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.warnings(),
+            vec![
+                "Line 5: The \"represents\" paragraph for <table> is never used by a <!--REPRESENTS--> placeholder.",
+                "Line 6: The <stool> element has no \"represents\" paragraph.",
+            ]
+        );
+        proc.apply()?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_represents_duplicate() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<p>The <code>chair</code> element <span>represents</span> a seat.
+<p>The <code>chair</code> element <span>represents</span> a throne.
+<p><!--REPRESENTS chair-->"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.warnings(),
+            vec![
+                "Line 3: Duplicate \"represents\" paragraph for <chair> (previous one on line 2)."
+            ]
+        );
+        proc.apply()?;
+        assert_eq!(
+            serialize_for_test(&[document]),
+            "<!DOCTYPE html><html><head></head><body><p>The <code>chair</code> element <span>represents</span> a seat.\n</p><p>The <code>chair</code> element <span>represents</span> a throne.\n</p><p>A throne.\n</p></body></html>"
+        );
+        Ok(())
+    }
 }
@@ -1,26 +1,116 @@
 //! Inserts `<a class="self-link" href="#ID">` links for elements with `id` attributes and certain classes.
+//!
+//! Which elements get links, and what the links look like, can be configured so
+//! that other specs built with this tool can use it too. Headings, <dfn>s and
+//! the entries of <dl class="domintro"> can also be opted in. Since Wattsi
+//! only gives most <dfn>s their IDs later, opting in <dfn>s is only useful for
+//! specs which give them IDs in the source.
+//!
+//! Elements with a target class but no `id` are skipped, unless IDs are to be
+//! derived for them. Derived IDs are deterministic: the ID of the nearest
+//! preceding heading, the class, and the ordinal within that section, e.g.
+//! `the-a-element:example-3`.
+
+use std::collections::{HashMap, HashSet};
 
 use html5ever::tendril::StrTendril;
 use html5ever::{QualName, local_name, ns};
 use markup5ever_rcdom::Handle;
 use url::Url;
 
-use crate::dom_utils::NodeHandleExt;
+use crate::dom_utils::{self, NodeHandleExt};
+
+const ID: QualName = QualName {
+    prefix: None,
+    ns: ns!(),
+    local: local_name!("id"),
+};
+
+pub struct Config {
+    /// Elements with any of these classes get a self-link.
+    pub target_classes: Vec<String>,
+
+    /// The URL of the spec, which IDs are percent-encoded relative to.
+    pub base_url: Url,
+
+    /// The class of the inserted links.
+    pub link_class: String,
+
+    /// Whether headings get a self-link, at the end, containing `heading_marker`.
+    pub headings: bool,
+
+    /// The text of the self-links appended to headings.
+    pub heading_marker: String,
+
+    /// Whether <dfn>s with an ID get a self-link, just after the <dfn> so that
+    /// its content is unchanged.
+    pub dfns: bool,
+
+    /// Whether the <dt>s of <dl class="domintro"> get a self-link.
+    pub domintro: bool,
+
+    /// Whether elements with a target class but no ID get a derived ID,
+    /// rather than being skipped.
+    pub derive_missing_ids: bool,
+}
 
-const TARGET_CLASSES: &[&str] = &["example", "note", "XXX"];
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            target_classes: vec!["example".to_owned(), "note".to_owned(), "XXX".to_owned()],
+            base_url: Url::parse("https://html.spec.whatwg.org/multipage/").unwrap(),
+            link_class: "self-link".to_owned(),
+            headings: false,
+            heading_marker: "§".to_owned(),
+            dfns: false,
+            domintro: false,
+            derive_missing_ids: false,
+        }
+    }
+}
 
 enum Edit {
     InsertAsFirstChild(Handle, StrTendril),
+    InsertAfter(Handle, StrTendril),
+    AppendWithMarker(Handle, StrTendril),
     InsertAfterSummary(Handle, StrTendril),
 }
 
 pub struct Processor {
+    config: Config,
     edits: Vec<Edit>,
+
+    /// The ID of the most recent heading which has one.
+    section: Option<StrTendril>,
+
+    /// Every ID in the document, so that derived IDs don't collide with them.
+    ids: HashSet<String>,
+
+    /// Elements which need a derived ID, with the section and class it is
+    /// derived from.
+    missing_ids: Vec<(Handle, Option<StrTendril>, String)>,
+}
+
+impl Default for Processor {
+    fn default() -> Self {
+        Self::with_config(Config::default())
+    }
 }
 
 impl Processor {
+    /// Creates a processor with the default configuration, i.e., HTML's.
     pub fn new() -> Self {
-        Self { edits: vec![] }
+        Self::default()
+    }
+
+    pub fn with_config(config: Config) -> Self {
+        Self {
+            config,
+            edits: vec![],
+            section: None,
+            ids: HashSet::new(),
+            missing_ids: vec![],
+        }
     }
 
     pub fn visit(&mut self, node: &Handle) {
@@ -28,32 +118,117 @@ impl Processor {
             return;
         }
 
-        if !node.has_any_class(TARGET_CLASSES) {
+        let id = node.get_attribute(&ID);
+        if let Some(ref id) = id {
+            self.ids.insert(id.to_string());
+            if dom_utils::heading_level(node).is_some() {
+                self.section = Some(id.clone());
+            }
+        }
+
+        let is_heading = self.config.headings && dom_utils::heading_level(node).is_some();
+        let is_dfn = self.config.dfns && node.is_html_element(&local_name!("dfn"));
+        let is_target = self
+            .config
+            .target_classes
+            .iter()
+            .any(|class| node.has_class(class))
+            || is_heading
+            || is_dfn
+            || (self.config.domintro
+                && node.is_html_element(&local_name!("dt"))
+                && node.parent_node().is_some_and(|p| {
+                    p.is_html_element(&local_name!("dl")) && p.has_class("domintro")
+                }));
+        if !is_target {
             return;
         }
 
-        if node.any_child(|c| c.has_class("self-link")) {
+        if node.any_child(|c| c.has_class(&self.config.link_class))
+            || (is_dfn && next_sibling(node).is_some_and(|s| s.has_class(&self.config.link_class)))
+        {
             return;
         }
 
-        if let Some(id) = node.get_attribute(&QualName::new(None, ns!(), local_name!("id"))) {
-            if node.is_html_element(&local_name!("details")) {
-                self.edits.push(Edit::InsertAfterSummary(node.clone(), id));
-            } else {
-                self.edits.push(Edit::InsertAsFirstChild(node.clone(), id));
+        match id {
+            Some(id) if is_heading => self.edits.push(Edit::AppendWithMarker(node.clone(), id)),
+            Some(id) if is_dfn => self.edits.push(Edit::InsertAfter(node.clone(), id)),
+            Some(id) => self.edits.push(Self::edit_for(node, id)),
+            None if self.config.derive_missing_ids => {
+                if let Some(class) = self
+                    .config
+                    .target_classes
+                    .iter()
+                    .find(|class| node.has_class(class))
+                {
+                    self.missing_ids.push((
+                        node.clone(),
+                        self.section.clone(),
+                        class.to_lowercase(),
+                    ));
+                }
             }
+            None => (),
         }
     }
 
-    pub fn apply(self) -> std::io::Result<()> {
-        for edit in self.edits {
+    fn edit_for(node: &Handle, id: StrTendril) -> Edit {
+        if node.is_html_element(&local_name!("details")) {
+            Edit::InsertAfterSummary(node.clone(), id)
+        } else {
+            Edit::InsertAsFirstChild(node.clone(), id)
+        }
+    }
+
+    /// Returns the elements which will be given derived IDs, with those IDs.
+    /// Should be called after visiting the document.
+    pub fn derived_ids(&self) -> Vec<(Handle, String)> {
+        let mut ordinals: HashMap<(Option<&str>, &str), u32> = HashMap::new();
+        let mut taken = self.ids.clone();
+        let mut derived = Vec::new();
+        for (node, section, class) in self.missing_ids.iter() {
+            let ordinal = ordinals.entry((section.as_deref(), class)).or_insert(0);
+            let id = loop {
+                *ordinal += 1;
+                let id = match section {
+                    Some(section) => format!("{section}:{class}-{ordinal}"),
+                    None => format!("{class}-{ordinal}"),
+                };
+                if taken.insert(id.clone()) {
+                    break id;
+                }
+            };
+            derived.push((node.clone(), id));
+        }
+        derived
+    }
+
+    pub fn apply(mut self) -> std::io::Result<()> {
+        for (node, id) in self.derived_ids() {
+            let id = StrTendril::from(id);
+            node.set_attribute(&ID, id.clone());
+            self.edits.push(Self::edit_for(&node, id));
+        }
+        for edit in self.edits.iter() {
             match edit {
                 Edit::InsertAsFirstChild(node, id) => {
-                    let link = create_self_link(&id);
+                    let link = self.create_self_link(id, None);
                     node.prepend_child(link);
                 }
+                Edit::InsertAfter(node, id) => {
+                    let link = self.create_self_link(id, None);
+                    let parent = node.parent_node().expect("should have a parent");
+                    match next_sibling(node) {
+                        Some(next) => parent.insert_children_before(&next, std::iter::once(link)),
+                        None => parent.append_children(std::iter::once(link)),
+                    }
+                }
+                Edit::AppendWithMarker(node, id) => {
+                    let link = self.create_self_link(id, Some(&self.config.heading_marker));
+                    node.append_children(std::iter::once(link));
+                }
                 Edit::InsertAfterSummary(node, id) => {
-                    let link = create_self_link(&id);
+                    let link = self.create_self_link(id, None);
                     let summary = node
                         .children
                         .borrow()
@@ -79,17 +254,27 @@ impl Processor {
         }
         Ok(())
     }
-}
 
-fn create_self_link(id: &str) -> Handle {
-    let mut url = Url::parse("https://html.spec.whatwg.org/multipage/").unwrap();
-    url.set_fragment(Some(id));
-    let href = url.fragment().unwrap_or("");
+    fn create_self_link(&self, id: &str, text: Option<&str>) -> Handle {
+        let mut url = self.config.base_url.clone();
+        url.set_fragment(Some(id));
+        let href = url.fragment().unwrap_or("");
+
+        let link = Handle::create_element(local_name!("a"))
+            .attribute(&local_name!("href"), format!("#{href}"))
+            .attribute(&local_name!("class"), self.config.link_class.clone());
+        match text {
+            Some(text) => link.text(text).build(),
+            None => link.build(),
+        }
+    }
+}
 
-    Handle::create_element(local_name!("a"))
-        .attribute(&local_name!("href"), format!("#{href}"))
-        .attribute(&local_name!("class"), "self-link")
-        .build()
+fn next_sibling(node: &Handle) -> Option<Handle> {
+    let parent = node.parent_node()?;
+    let children = parent.children.borrow();
+    let i = children.iter().position(|c| std::rc::Rc::ptr_eq(c, node))?;
+    children.get(i + 1).cloned()
 }
 
 #[cfg(test)]
@@ -209,6 +394,102 @@ mod tests {
         assert_eq!(
             serialize_for_test(&[document]),
             r##"<!DOCTYPE html><html><head></head><body><div class="example" id="foo bar"><a href="#foo%20bar" class="self-link"></a></div>
+</body></html>"##
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_config() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r##"<!DOCTYPE html>
+<h2 id="intro">Introduction</h2>
+<div class="example" id="foo"></div>
+<div class="warning" id="bar"></div>
+<p>A <dfn id="dfn-widget">widget</dfn> is...
+<dl class="domintro"><dt id="dom-widget-spin"><code>widget.spin()</code></dt><dd>Spins.</dd></dl>
+<dl><dt id="not-domintro">Term</dt></dl>
+"##
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+
+        let mut processor = Processor::with_config(Config {
+            target_classes: vec!["warning".to_owned()],
+            base_url: Url::parse("https://example.spec.whatwg.org/").unwrap(),
+            link_class: "anchor".to_owned(),
+            headings: true,
+            dfns: true,
+            domintro: true,
+            ..Default::default()
+        });
+        dom_utils::scan_dom(&document, &mut |h| processor.visit(h));
+        processor.apply()?;
+
+        assert_eq!(
+            serialize_for_test(&[document]),
+            r##"<!DOCTYPE html><html><head></head><body><h2 id="intro">Introduction<a href="#intro" class="anchor">§</a></h2>
+<div class="example" id="foo"></div>
+<div class="warning" id="bar"><a href="#bar" class="anchor"></a></div>
+<p>A <dfn id="dfn-widget">widget</dfn><a href="#dfn-widget" class="anchor"></a> is...
+</p><dl class="domintro"><dt id="dom-widget-spin"><a href="#dom-widget-spin" class="anchor"></a><code>widget.spin()</code></dt><dd>Spins.</dd></dl>
+<dl><dt id="not-domintro">Term</dt></dl>
+</body></html>"##
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_derive_missing_ids() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r##"<!DOCTYPE html>
+<div class="example">Before any section.</div>
+<h4 id="the-a-element">The a element</h4>
+<div class="note">A note.</div>
+<div class="example">One.</div>
+<div class="example" id="the-a-element:example-2">Two.</div>
+<div class="example">Three.</div>
+<h4>No ID</h4>
+<details class="example"><summary>Four</summary></details>
+"##
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+
+        let mut processor = Processor::with_config(Config {
+            derive_missing_ids: true,
+            ..Default::default()
+        });
+        dom_utils::scan_dom(&document, &mut |h| processor.visit(h));
+        let derived: Vec<String> = processor
+            .derived_ids()
+            .into_iter()
+            .map(|(_, id)| id)
+            .collect();
+        assert_eq!(
+            derived,
+            vec![
+                "example-1",
+                "the-a-element:note-1",
+                "the-a-element:example-1",
+                "the-a-element:example-3",
+                "the-a-element:example-4",
+            ]
+        );
+        processor.apply()?;
+
+        assert_eq!(
+            serialize_for_test(&[document]),
+            r##"<!DOCTYPE html><html><head></head><body><div class="example" id="example-1"><a href="#example-1" class="self-link"></a>Before any section.</div>
+<h4 id="the-a-element">The a element</h4>
+<div class="note" id="the-a-element:note-1"><a href="#the-a-element:note-1" class="self-link"></a>A note.</div>
+<div class="example" id="the-a-element:example-1"><a href="#the-a-element:example-1" class="self-link"></a>One.</div>
+<div class="example" id="the-a-element:example-2"><a href="#the-a-element:example-2" class="self-link"></a>Two.</div>
+<div class="example" id="the-a-element:example-3"><a href="#the-a-element:example-3" class="self-link"></a>Three.</div>
+<h4>No ID</h4>
+<details class="example" id="the-a-element:example-4"><summary>Four</summary><a href="#the-a-element:example-4" class="self-link"></a></details>
 </body></html>"##
         );
         Ok(())
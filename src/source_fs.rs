@@ -0,0 +1,293 @@
+//! Abstracts over where source files, such as boilerplate and demos, are read
+//! from. They can come from a directory on disk, from memory (e.g., a git tree
+//! object or a request body on the build server), or from one layered over the
+//! other.
+
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::task::JoinHandle;
+
+use crate::io_utils::is_safe_path;
+
+/// A read-only tree of files, addressed by relative paths which may come from a
+/// potentially untrusted source.
+pub trait SourceFs: Send + Sync {
+    /// Reads the entire file at the given relative path. Implementations must
+    /// not allow the path to escape the tree.
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Describes the root of the tree, for use in error messages.
+    fn describe_root(&self) -> String;
+
+    /// Describes where the file at the given relative path is looked for, for
+    /// use in error messages.
+    fn describe_path(&self, path: &Path) -> String;
+
+    /// Reads the entire file at the given relative path, which must be UTF-8.
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// In a blocking task, read the file.
+pub fn spawn_read(fs: &Arc<dyn SourceFs>, path: &Path) -> JoinHandle<io::Result<Vec<u8>>> {
+    let fs = fs.clone();
+    let path = path.to_owned();
+    tokio::task::spawn_blocking(move || fs.read(&path))
+}
+
+/// In a blocking task, read the file to a string.
+pub fn spawn_read_to_string(fs: &Arc<dyn SourceFs>, path: &Path) -> JoinHandle<io::Result<String>> {
+    let fs = fs.clone();
+    let path = path.to_owned();
+    tokio::task::spawn_blocking(move || fs.read_to_string(&path))
+}
+
+fn unsafe_path_error(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::PermissionDenied,
+        format!(
+            "cannot traverse to a parent directory in {}",
+            path.display()
+        ),
+    )
+}
+
+/// Files in a directory on disk. Paths are checked lexically, and then again
+/// once symlinks have been resolved, so that they cannot escape the directory.
+#[derive(Clone, Debug)]
+pub struct DiskFs {
+    root: PathBuf,
+}
+
+impl DiskFs {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Resolves a relative path to a canonical path within the directory.
+    pub fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        if !is_safe_path(path) {
+            return Err(unsafe_path_error(path));
+        }
+        let root = self.root.canonicalize()?;
+        let resolved = root.join(path).canonicalize()?;
+        if !resolved.starts_with(&root) {
+            return Err(io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                format!(
+                    "{} resolves to {}, which is outside {}",
+                    path.display(),
+                    resolved.display(),
+                    root.display()
+                ),
+            ));
+        }
+        Ok(resolved)
+    }
+}
+
+impl SourceFs for DiskFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(self.resolve(path)?)
+    }
+
+    fn describe_root(&self) -> String {
+        self.root.display().to_string()
+    }
+
+    fn describe_path(&self, path: &Path) -> String {
+        match self.resolve(path) {
+            Ok(resolved) => resolved.display().to_string(),
+            Err(_) => self.root.join(path).display().to_string(),
+        }
+    }
+}
+
+/// Files held in memory.
+#[derive(Clone, Debug, Default)]
+pub struct MemoryFs {
+    files: HashMap<PathBuf, Vec<u8>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Adds (or replaces) a file. Returns self, for chaining.
+    pub fn with_file(mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, contents);
+        self
+    }
+
+    /// Adds (or replaces) a file.
+    pub fn insert(&mut self, path: impl AsRef<Path>, contents: impl Into<Vec<u8>>) {
+        self.files.insert(normalize(path.as_ref()), contents.into());
+    }
+}
+
+/// Drops "." components, so that "a/./b" and "a/b" name the same file.
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|c| !matches!(c, std::path::Component::CurDir))
+        .collect()
+}
+
+impl SourceFs for MemoryFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        if !is_safe_path(path) {
+            return Err(unsafe_path_error(path));
+        }
+        self.files.get(&normalize(path)).cloned().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} not found in memory", path.display()),
+            )
+        })
+    }
+
+    fn describe_root(&self) -> String {
+        "files in memory".to_owned()
+    }
+
+    fn describe_path(&self, path: &Path) -> String {
+        format!("{} in memory", normalize(path).display())
+    }
+}
+
+/// Files from an upper tree, falling back to a lower tree for any which the
+/// upper one does not have.
+pub struct OverlayFs {
+    upper: Arc<dyn SourceFs>,
+    lower: Arc<dyn SourceFs>,
+}
+
+impl OverlayFs {
+    pub fn new(upper: Arc<dyn SourceFs>, lower: Arc<dyn SourceFs>) -> Self {
+        Self { upper, lower }
+    }
+}
+
+impl SourceFs for OverlayFs {
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.upper.read(path) {
+            Err(e) if e.kind() == io::ErrorKind::NotFound => self.lower.read(path),
+            result => result,
+        }
+    }
+
+    fn describe_root(&self) -> String {
+        format!(
+            "{} overlaid on {}",
+            self.upper.describe_root(),
+            self.lower.describe_root()
+        )
+    }
+
+    fn describe_path(&self, path: &Path) -> String {
+        format!(
+            "{} or {}",
+            self.upper.describe_path(path),
+            self.lower.describe_path(path)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_disk_fs_reads_inside_root() -> io::Result<()> {
+        let temp_dir = TempDir::new()?;
+        std::fs::create_dir(temp_dir.path().join("sub"))?;
+        std::fs::write(temp_dir.path().join("sub/a.txt"), "inside")?;
+        let fs = DiskFs::new(temp_dir.path());
+        assert_eq!(fs.read_to_string(Path::new("sub/./a.txt"))?, "inside");
+        let result = fs.read(Path::new("../a.txt"));
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::PermissionDenied));
+        let result = fs.read(Path::new("missing.txt"));
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::NotFound));
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_disk_fs_symlinks() -> io::Result<()> {
+        use std::os::unix::fs::symlink;
+
+        let temp_dir = TempDir::new()?;
+        let root = temp_dir.path().join("root");
+        let outside = temp_dir.path().join("outside");
+        std::fs::create_dir_all(root.join("sub"))?;
+        std::fs::create_dir(&outside)?;
+        std::fs::write(root.join("sub/a.txt"), "inside")?;
+        std::fs::write(outside.join("secret.txt"), "secret")?;
+
+        // Symlinks which stay inside the root are fine.
+        symlink(root.join("sub"), root.join("alias"))?;
+        // These ones escape, directly or by way of a ".." in their target.
+        symlink(&outside, root.join("escape"))?;
+        symlink(outside.join("secret.txt"), root.join("secret"))?;
+        symlink("../..", root.join("sub/up"))?;
+        symlink("../../outside/secret.txt", root.join("sub/file"))?;
+
+        let fs = DiskFs::new(&root);
+        assert_eq!(fs.read_to_string(Path::new("alias/a.txt"))?, "inside");
+        // None of these paths has a ".." of its own, so they can only be
+        // rejected once the symlinks have been resolved.
+        for path in [
+            "escape/secret.txt",
+            "secret",
+            "sub/up/outside/secret.txt",
+            "sub/file",
+        ] {
+            assert!(is_safe_path(path), "{path}");
+            let result = fs.resolve(Path::new(path));
+            assert!(
+                matches!(&result, Err(e) if e.kind() == io::ErrorKind::PermissionDenied
+                    && e.to_string().contains("which is outside")),
+                "{path}: {result:?}"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_fs() -> io::Result<()> {
+        let fs = MemoryFs::new()
+            .with_file("a.txt", "first")
+            .with_file("dir/./b.txt", "second");
+        assert_eq!(fs.read_to_string(Path::new("./a.txt"))?, "first");
+        assert_eq!(fs.read_to_string(Path::new("dir/b.txt"))?, "second");
+        let result = fs.read(Path::new("c.txt"));
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::NotFound));
+        let result = fs.read(Path::new("dir/../a.txt"));
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::PermissionDenied));
+        let result = MemoryFs::new()
+            .with_file("bad", [0xff, 0xfe])
+            .read_to_string(Path::new("bad"));
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::InvalidData));
+        Ok(())
+    }
+
+    #[test]
+    fn test_overlay_fs() -> io::Result<()> {
+        let lower = MemoryFs::new()
+            .with_file("a.txt", "lower a")
+            .with_file("b.txt", "lower b");
+        let upper = MemoryFs::new().with_file("a.txt", "upper a");
+        let fs = OverlayFs::new(Arc::new(upper), Arc::new(lower));
+        assert_eq!(fs.read_to_string(Path::new("a.txt"))?, "upper a");
+        assert_eq!(fs.read_to_string(Path::new("b.txt"))?, "lower b");
+        let result = fs.read(Path::new("c.txt"));
+        assert!(matches!(result, Err(e) if e.kind() == io::ErrorKind::NotFound));
+        Ok(())
+    }
+}
@@ -1,10 +1,14 @@
 //! Looks at the "Optional tags" and "Void elements" sections from the HTML
 //! syntax spec and replicates that information into the descriptions of the
 //! individual elements.
+//!
+//! It can also check that information against how html5ever actually parses
+//! each element.
 
 use std::borrow::Borrow;
 use std::collections::HashMap;
 use std::io;
+use std::rc::Rc;
 
 use html5ever::tendril::StrTendril;
 use html5ever::{LocalName, QualName, local_name, ns};
@@ -12,6 +16,9 @@ use markup5ever_rcdom::{Handle, NodeData};
 use regex::Regex;
 
 use crate::dom_utils::{self, NodeHandleExt, heading_level};
+use crate::omission_rules::{OmissionRule, Tag};
+use crate::parser;
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, format_warnings, line_prefix};
 
 #[derive(Default)]
 struct ElementInfo {
@@ -25,21 +32,117 @@ struct ElementInfo {
     dl: Option<Handle>,
 }
 
-#[derive(Default)]
-pub struct Processor {
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// The <dfn>Void elements</dfn>, if found.
+    void_elements_dfn: Option<Handle>,
+
     /// The heading level of the "Optional tags" heading, if inside one.
     in_optional_tags_heading: Option<u8>,
 
-    /// Most recently seen <dfn element>.
-    most_recent_element_dfn: Option<StrTendril>,
+    /// Finds the <dl class="element"> for each element.
+    element_dls: dom_utils::ElementDlTracker,
 
     /// Info about elements which have been referred to in these sections.
     elements: HashMap<StrTendril, ElementInfo>,
+
+    /// Any <dl class="element"> which no <dfn element> precedes.
+    orphan_dls: Vec<Handle>,
+}
+
+/// Returns the "Content attributes" <dt>, before which the tag omission
+/// information is inserted.
+fn attributes_dt(dl: &Handle) -> Option<Handle> {
+    let data_x = QualName::new(None, ns!(), LocalName::from("data-x"));
+    dl.children
+        .borrow()
+        .iter()
+        .find(|child| {
+            child.is_html_element(&local_name!("dt"))
+                && child.any_child(|c| c.attribute_is(&data_x, "concept-element-attributes"))
+        })
+        .cloned()
 }
 
-impl Processor {
-    pub fn new() -> Self {
-        Default::default()
+/// Elements which need to be inside others to be parsed as they normally would
+/// be, and the markup which opens and closes those.
+fn probe_context(element: &str) -> (&'static str, &'static str) {
+    match element {
+        "col" => ("<table><colgroup>", "</colgroup></table>"),
+        "tr" => ("<table><tbody>", "</tbody></table>"),
+        "td" | "th" => ("<table><tbody><tr>", "</tr></tbody></table>"),
+        "caption" | "colgroup" | "tbody" | "thead" | "tfoot" => ("<table>", "</table>"),
+        "rt" | "rp" => ("<ruby>", "</ruby>"),
+        "option" | "optgroup" => ("<select>", "</select>"),
+        _ => ("", ""),
+    }
+}
+
+/// Parses `markup` in a document, in the right context for `element`, and
+/// returns it with each `element` created, in tree order. (The document must be
+/// kept alive, since dropping it empties its descendants.)
+async fn parse_probe(
+    element: &str,
+    markup: &str,
+) -> io::Result<(RcDomWithLineNumbers, Vec<Handle>)> {
+    let (open, close) = probe_context(element);
+    let source = format!("<!DOCTYPE html><body>{open}{markup}{close}");
+    let parsed = parser::parse_document_unchecked_async(source.as_bytes()).await?;
+    let name = LocalName::from(element);
+    let mut found = Vec::new();
+    dom_utils::scan_dom(parsed.document(), &mut |n| {
+        if n.is_html_element(&name) {
+            found.push(n.clone());
+        }
+    });
+    Ok((parsed, found))
+}
+
+/// Whether the parser never puts anything inside the element.
+async fn parser_treats_as_void(element: &str) -> io::Result<bool> {
+    let (_parsed, found) = parse_probe(element, &format!("<{element}><!--x-->")).await?;
+    Ok(found.first().is_some_and(|e| {
+        let has_template_contents = match e.data {
+            NodeData::Element {
+                ref template_contents,
+                ..
+            } => template_contents
+                .borrow()
+                .as_ref()
+                .is_some_and(|tc| !tc.children.borrow().is_empty()),
+            _ => false,
+        };
+        e.children.borrow().is_empty() && !has_template_contents
+    }))
+}
+
+/// Whether the parser, without error, ends the element when another of the same
+/// kind starts.
+async fn parser_implies_end_tag(element: &str) -> io::Result<bool> {
+    let (parsed, found) =
+        parse_probe(element, &format!("<{element}><!--a--><{element}><!--b-->")).await?;
+    Ok(parsed.parse_error_messages().is_empty()
+        && match &found[..] {
+            [a, b] => a
+                .parent_node()
+                .zip(b.parent_node())
+                .is_some_and(|(x, y)| Rc::ptr_eq(&x, &y)),
+            _ => false,
+        })
+}
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            void_elements_dfn: None,
+            in_optional_tags_heading: None,
+            element_dls: Default::default(),
+            elements: HashMap::new(),
+            orphan_dls: Vec::new(),
+        }
     }
 
     pub fn visit(&mut self, node: &Handle) {
@@ -71,6 +174,7 @@ impl Processor {
                 .parent_node()
                 .filter(|n| n.is_html_element(&local_name!("dt")))
         {
+            self.void_elements_dfn = Some(node.clone());
             for dd in dom_utils::dt_descriptions(&dt) {
                 dom_utils::scan_dom(&dd, &mut |n| {
                     if n.is_html_element(&local_name!("code")) {
@@ -81,22 +185,19 @@ impl Processor {
             }
         }
 
-        // If we see an element dfn, watch out for the upcoming <dl class="element">.
-        if node.is_html_element(&local_name!("dfn"))
-            && node.has_attribute(&QualName::new(None, ns!(), LocalName::from("element")))
-        {
-            self.most_recent_element_dfn = Some(node.text_content());
-        }
-
-        // If we see a <dl class="element">, record that.
-        if node.is_html_element(&local_name!("dl"))
-            && node.has_class("element")
-            && let Some(elem) = std::mem::take(&mut self.most_recent_element_dfn)
-        {
-            let info = self.elements.entry(elem).or_default();
-            if info.dl.is_none() {
-                info.dl = Some(node.clone());
+        // If we see a <dl class="element">, record that against the element it
+        // was most recently preceded by.
+        match self.element_dls.visit(node).and_then(|mut e| e.pop()) {
+            Some(elem) => {
+                let info = self.elements.entry(elem).or_default();
+                if info.dl.is_none() {
+                    info.dl = Some(node.clone());
+                }
             }
+            None if node.is_html_element(&local_name!("dl")) && node.has_class("element") => {
+                self.orphan_dls.push(node.clone());
+            }
+            None => (),
         }
     }
 
@@ -119,9 +220,115 @@ impl Processor {
         }
     }
 
+    /// Returns a description of each place where tag omission information
+    /// cannot be added, because the element definition is missing or is not in
+    /// the expected form, and of each "Optional tags" paragraph which cannot
+    /// be parsed as a rule. Should be called after visiting the document.
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings: Vec<(Option<u64>, String)> = Vec::new();
+        for dl in self.orphan_dls.iter() {
+            warnings.push((
+                self.parsed.line_number_for(dl),
+                "<dl class=\"element\"> is not preceded by a <dfn element>.".to_owned(),
+            ));
+        }
+        for (name, info) in self.elements.iter() {
+            for p in info.optional_tags_info.iter() {
+                if OmissionRule::parse(p).is_none() {
+                    warnings.push((
+                        self.parsed.line_number_for(p),
+                        format!("\"Optional tags\" paragraph for <{name}> cannot be parsed as a rule, so it is copied as is and left out of the exported rules."),
+                    ));
+                }
+            }
+            match info.dl {
+                Some(ref dl) if attributes_dt(dl).is_none() => warnings.push((
+                    self.parsed.line_number_for(dl),
+                    format!("<dl class=\"element\"> for <{name}> has no \"Content attributes\" <dt>, so tag omission information cannot be added."),
+                )),
+                Some(_) => (),
+                None => {
+                    for p in info.optional_tags_info.iter() {
+                        warnings.push((
+                            self.parsed.line_number_for(p),
+                            format!("\"Optional tags\" paragraph refers to <{name}>, which has no <dl class=\"element\">."),
+                        ));
+                    }
+                }
+            }
+        }
+        format_warnings(warnings)
+    }
+
+    /// Returns the rules from the "Optional tags" section which could be parsed,
+    /// ordered by element.
+    pub fn rules(&self) -> Vec<OmissionRule> {
+        let mut names: Vec<&StrTendril> = self.elements.keys().collect();
+        names.sort();
+        names
+            .into_iter()
+            .flat_map(|name| self.elements[name].optional_tags_info.iter())
+            .filter_map(OmissionRule::parse)
+            .collect()
+    }
+
+    /// Compares the "Void elements" list and the elements whose end tags the
+    /// "Optional tags" section says may be omitted with the behavior of
+    /// html5ever's parser, and describes any differences. The html, head and
+    /// body elements are skipped, since the parser always creates them.
+    pub async fn parser_discrepancies(&self) -> io::Result<Vec<String>> {
+        let line_prefix =
+            |node: Option<&Handle>| line_prefix(node.and_then(|n| self.parsed.line_number_for(n)));
+
+        let mut names: Vec<&StrTendril> = self
+            .elements
+            .keys()
+            .filter(|e| !matches!(&e[..], "html" | "head" | "body"))
+            .collect();
+        names.sort();
+        let mut discrepancies = Vec::new();
+        for name in names {
+            let info = &self.elements[name];
+            let parser_void = parser_treats_as_void(name).await?;
+            if parser_void != info.is_void_element {
+                discrepancies.push(if parser_void {
+                    format!(
+                        "{}<{name}> is void when parsed, but is not listed as a void element.",
+                        line_prefix(info.dl.as_ref().or(self.void_elements_dfn.as_ref()))
+                    )
+                } else {
+                    format!(
+                        "{}<{name}> is listed as a void element, but is not void when parsed.",
+                        line_prefix(self.void_elements_dfn.as_ref())
+                    )
+                });
+            }
+            if parser_void {
+                continue;
+            }
+
+            let end_tag_paragraph = info
+                .optional_tags_info
+                .iter()
+                .find(|p| OmissionRule::parse(p).is_some_and(|r| r.tag == Tag::End));
+            let parser_omissible = parser_implies_end_tag(name).await?;
+            match (end_tag_paragraph, parser_omissible) {
+                (Some(p), false) => discrepancies.push(format!(
+                    "{}<{name}> end tag is said to be omissible, but the parser does not imply it.",
+                    line_prefix(Some(p))
+                )),
+                (None, true) => discrepancies.push(format!(
+                    "{}<{name}> end tag is implied by the parser, but is not said to be omissible.",
+                    line_prefix(info.dl.as_ref())
+                )),
+                _ => (),
+            }
+        }
+        Ok(discrepancies)
+    }
+
     pub fn apply(self) -> io::Result<()> {
         let data_x = LocalName::from("data-x");
-        let qual_data_x = QualName::new(None, ns!(), data_x.clone());
         let dt = Handle::create_element(local_name!("dt"))
             .child(
                 Handle::create_element(local_name!("span"))
@@ -154,21 +361,23 @@ impl Processor {
 
             let mut to_insert = vec![dt.deep_clone()];
             if !info.optional_tags_info.is_empty() {
-                // Convert <p> to <dd>, replacing "may" with "can".
+                // Convert <p> to <dd>, regenerating the rule where it can be
+                // parsed, and replacing "may" with "can".
                 for p in info.optional_tags_info {
-                    let borrowed_children = p.children.borrow();
-                    let new_children = borrowed_children.iter().map(|n| {
-                        let new_node = n.deep_clone();
-                        dom_utils::scan_dom(&new_node, &mut |c| {
+                    let new_children = match OmissionRule::parse(&p) {
+                        Some(rule) => rule.to_nodes(),
+                        None => p.children.borrow().iter().map(|n| n.deep_clone()).collect(),
+                    };
+                    for new_node in new_children.iter() {
+                        dom_utils::scan_dom(new_node, &mut |c| {
                             if let NodeData::Text { ref contents } = c.data {
                                 let mut text = contents.borrow_mut();
                                 *text = StrTendril::from(may_re.replace(&text, "can").borrow());
                             }
                         });
-                        new_node
-                    });
+                    }
                     let dd = Handle::create_element(local_name!("dd"))
-                        .children(new_children)
+                        .children(new_children.into_iter())
                         .build();
                     to_insert.push(dd);
                 }
@@ -179,17 +388,9 @@ impl Processor {
             }
             to_insert.push(Handle::create_text_node("\n"));
 
-            let dl_children = dl.children.borrow();
-            let attributes_dt = if let Some(attributes_dt) = dl_children.iter().find(|child| {
-                child.is_html_element(&local_name!("dt"))
-                    && child
-                        .any_child(|c| c.attribute_is(&qual_data_x, "concept-element-attributes"))
-            }) {
-                attributes_dt.clone()
-            } else {
+            let Some(attributes_dt) = attributes_dt(&dl) else {
                 continue;
             };
-            drop(dl_children);
             dl.insert_children_before(&attributes_dt, to_insert.into_iter());
         }
         Ok(())
@@ -253,7 +454,7 @@ mod tests {
         )
         .await?;
         let document = parsed.document().clone();
-        let mut proc = Processor::new();
+        let mut proc = Processor::new(&parsed);
         dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
         proc.apply()?;
         assert_eq!(
@@ -310,4 +511,78 @@ mod tests {
             "#.trim());
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_parser_discrepancies() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<h3>Optional tags</h3>
+<p>An <code>li</code> element's end tag may be omitted if the <code>li</code> element is immediately followed by another <code>li</code> element.</p>
+<p>A <code>div</code> element's end tag may be omitted if the author is feeling lucky.</p>
+<p>A <code>td</code> element's start tag is never omissible.</p>
+<h3>Another section</h3>
+<dl>
+    <dt><dfn>Void elements</dfn>
+    <dd><code>br</code>, <code>col</code>, <code>span</code>
+</dl>
+<p><dfn element>option</dfn>
+<dl class=element></dl>
+<p><dfn element>template</dfn>
+<dl class=element></dl>
+<p><dfn element>img</dfn>
+<dl class=element></dl>
+"#
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.parser_discrepancies().await?,
+            vec![
+                "Line 4: <div> end tag is said to be omissible, but the parser does not imply it.",
+                "Line 16: <img> is void when parsed, but is not listed as a void element.",
+                "Line 12: <option> end tag is implied by the parser, but is not said to be omissible.",
+                "Line 8: <span> is listed as a void element, but is not void when parsed.",
+                "<td> end tag is implied by the parser, but is not said to be omissible.",
+            ]
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_warnings() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<h3>Optional tags</h3>
+<p>A <code>blink</code> element's end tag may be omitted if nobody is looking.</p>
+<p>A <code>td</code> element's end tag may be omitted if the table is boring.</p>
+<p>A <code>td</code> element's start tag is never omitted.</p>
+<h3>Elements</h3>
+<dl class=element>
+<dt><span data-x=concept-element-attributes></span>
+</dl>
+<p><dfn element>td</dfn>
+<dl class=element>
+<dt><span data-x=concept-element-dom></span>
+</dl>
+"#
+            .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        assert_eq!(
+            proc.warnings(),
+            vec![
+                "Line 3: \"Optional tags\" paragraph refers to <blink>, which has no <dl class=\"element\">.",
+                "Line 5: \"Optional tags\" paragraph for <td> cannot be parsed as a rule, so it is copied as is and left out of the exported rules.",
+                "Line 7: <dl class=\"element\"> is not preceded by a <dfn element>.",
+                "Line 11: <dl class=\"element\"> for <td> has no \"Content attributes\" <dt>, so tag omission information cannot be added.",
+            ]
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,382 @@
+//! Replaces <!--COPY id--> comments with copies of the contents of the element
+//! with that ID, which must be marked with a `copy-source` attribute:
+//!   <p id="x" copy-source>...</p>
+//!
+//! Options may follow the ID:
+//!   capitalize    capitalizes the first letter of the copy
+//!   id-suffix=-s  appends "-s" to IDs within the copy, instead of removing them
+//!
+//! Copies may themselves contain <!--COPY--> comments, as long as they don't
+//! form a cycle. The `copy-source` attributes are removed from the output.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::rc::Rc;
+
+use html5ever::tendril::StrTendril;
+use html5ever::{LocalName, QualName, local_name, ns};
+use markup5ever_rcdom::{Handle, NodeData};
+
+use crate::dom_utils::{self, NodeHandleExt, capitalize};
+use crate::rcdom_with_line_numbers::{RcDomWithLineNumbers, line_prefix};
+
+struct Placeholder {
+    /// The <!--COPY--> comment.
+    comment: Handle,
+
+    /// The ID of the element to copy.
+    id: StrTendril,
+
+    /// Whether to capitalize the first letter of the copy.
+    capitalize: bool,
+
+    /// If set, IDs in the copy are rewritten with this suffix, rather than
+    /// removed.
+    id_suffix: Option<String>,
+
+    /// The IDs of the sources which contain this placeholder, if any.
+    containers: Vec<StrTendril>,
+}
+
+pub struct Processor<'a> {
+    /// Parser context (for line numbers).
+    parsed: &'a RcDomWithLineNumbers,
+
+    /// Elements marked with `copy-source`, keyed by ID.
+    sources: HashMap<StrTendril, Handle>,
+
+    /// Errors found while visiting, such as sources without an ID.
+    errors: Vec<String>,
+
+    /// List of <!--COPY--> comments to be replaced.
+    placeholders: Vec<Placeholder>,
+}
+
+fn copy_source_attr() -> QualName {
+    QualName::new(None, ns!(), LocalName::from("copy-source"))
+}
+
+const ID: QualName = QualName {
+    prefix: None,
+    ns: ns!(),
+    local: local_name!("id"),
+};
+
+impl<'a> Processor<'a> {
+    pub fn new(parsed: &'a RcDomWithLineNumbers) -> Self {
+        Self {
+            parsed,
+            sources: HashMap::new(),
+            errors: Vec::new(),
+            placeholders: Vec::new(),
+        }
+    }
+
+    fn line_prefix(&self, node: &Handle) -> String {
+        line_prefix(self.parsed.line_number_for(node))
+    }
+
+    pub fn visit(&mut self, node: &Handle) {
+        match node.data {
+            NodeData::Element { .. } if node.has_attribute(&copy_source_attr()) => {
+                match node.get_attribute(&ID) {
+                    Some(id) if self.sources.contains_key(&id) => {
+                        let line = self.line_prefix(node);
+                        self.errors.push(format!(
+                            "{line}copy-source ID \"{id}\" is used more than once."
+                        ));
+                    }
+                    Some(id) => {
+                        self.sources.insert(id, node.clone());
+                    }
+                    None => {
+                        let line = self.line_prefix(node);
+                        self.errors
+                            .push(format!("{line}copy-source element has no ID."));
+                    }
+                }
+            }
+            NodeData::Comment { ref contents } if contents.starts_with("COPY ") => {
+                let mut tokens = contents[5..].split_ascii_whitespace();
+                let id = match tokens.next() {
+                    Some(id) => StrTendril::from(id),
+                    None => {
+                        let line = self.line_prefix(node);
+                        self.errors
+                            .push(format!("{line}<!--{contents}--> does not name an ID."));
+                        return;
+                    }
+                };
+                let mut placeholder = Placeholder {
+                    comment: node.clone(),
+                    id,
+                    capitalize: false,
+                    id_suffix: None,
+                    containers: Vec::new(),
+                };
+                for option in tokens {
+                    match option.split_once('=') {
+                        None if option == "capitalize" => placeholder.capitalize = true,
+                        Some(("id-suffix", "")) => {
+                            // An empty suffix would duplicate the IDs.
+                            let line = self.line_prefix(node);
+                            self.errors
+                                .push(format!("{line}<!--{contents}--> has an empty id-suffix."));
+                        }
+                        Some(("id-suffix", suffix)) => {
+                            placeholder.id_suffix = Some(suffix.to_owned())
+                        }
+                        _ => {
+                            let line = self.line_prefix(node);
+                            self.errors.push(format!(
+                                "{line}<!--{contents}--> has unknown option {option:?}."
+                            ));
+                        }
+                    }
+                }
+                self.placeholders.push(placeholder);
+            }
+            _ => (),
+        }
+    }
+
+    pub fn apply(mut self) -> io::Result<()> {
+        for placeholder in self.placeholders.iter_mut() {
+            if !self.sources.contains_key(&placeholder.id) {
+                self.errors.push(format!(
+                    "{}<!--COPY {}--> refers to an unknown copy-source ID.",
+                    line_prefix(self.parsed.line_number_for(&placeholder.comment)),
+                    placeholder.id
+                ));
+            }
+            let mut ancestor = placeholder.comment.parent_node();
+            while let Some(a) = ancestor {
+                if let Some(id) = a
+                    .get_attribute(&ID)
+                    .filter(|id| self.sources.get(id).is_some_and(|s| Rc::ptr_eq(s, &a)))
+                {
+                    placeholder.containers.push(id);
+                }
+                ancestor = a.parent_node();
+            }
+        }
+        if !self.errors.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                self.errors.join("\n"),
+            ));
+        }
+
+        // Sources must be expanded before they are copied, so order them such
+        // that each comes after every source it copies from.
+        let mut dependencies: HashMap<&str, Vec<&str>> = HashMap::new();
+        for placeholder in self.placeholders.iter() {
+            for container in placeholder.containers.iter() {
+                dependencies
+                    .entry(container)
+                    .or_default()
+                    .push(&placeholder.id);
+            }
+        }
+        let mut order: Vec<&str> = Vec::new();
+        let mut done: HashSet<&str> = HashSet::new();
+        let mut ids: Vec<&str> = self.sources.keys().map(|id| &id[..]).collect();
+        ids.sort();
+        for id in ids {
+            visit_dependencies(id, &dependencies, &mut Vec::new(), &mut done, &mut order)?;
+        }
+
+        // Then replace the placeholders inside sources, in that order, followed
+        // by all the rest.
+        let mut placeholders: Vec<&Placeholder> = Vec::new();
+        for id in order {
+            placeholders.extend(
+                self.placeholders
+                    .iter()
+                    .filter(|p| p.containers.first().is_some_and(|c| &c[..] == id)),
+            );
+        }
+        placeholders.extend(self.placeholders.iter().filter(|p| p.containers.is_empty()));
+        for placeholder in placeholders {
+            let source = &self.sources[&placeholder.id];
+            let copies = source
+                .children
+                .borrow()
+                .iter()
+                .enumerate()
+                .map(|(index, child)| {
+                    let clone = child.deep_clone();
+                    dom_utils::scan_dom(&clone, &mut |n| {
+                        if !n.is_element() {
+                            return;
+                        }
+                        n.remove_attribute(&copy_source_attr());
+                        match (&placeholder.id_suffix, n.get_attribute(&ID)) {
+                            (Some(suffix), Some(mut id)) => {
+                                id.push_slice(suffix);
+                                n.set_attribute(&ID, id);
+                            }
+                            (None, Some(_)) => n.remove_attribute(&ID),
+                            (_, None) => (),
+                        }
+                    });
+                    if let (0, true, NodeData::Text { contents }) =
+                        (index, placeholder.capitalize, &clone.data)
+                    {
+                        contents.replace_with(|text| capitalize(text.trim_start()));
+                    }
+                    clone
+                })
+                .collect();
+            placeholder.comment.replace_with(copies);
+        }
+
+        for source in self.sources.values() {
+            source.remove_attribute(&copy_source_attr());
+        }
+        Ok(())
+    }
+}
+
+/// Depth-first traversal which appends each source to `order` after the
+/// sources it depends on, and reports an error if there is a cycle.
+fn visit_dependencies<'b>(
+    id: &'b str,
+    dependencies: &HashMap<&'b str, Vec<&'b str>>,
+    stack: &mut Vec<&'b str>,
+    done: &mut HashSet<&'b str>,
+    order: &mut Vec<&'b str>,
+) -> io::Result<()> {
+    if done.contains(id) {
+        return Ok(());
+    }
+    if let Some(start) = stack.iter().position(|s| *s == id) {
+        let cycle: Vec<&str> = stack[start..]
+            .iter()
+            .copied()
+            .chain(std::iter::once(id))
+            .collect();
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("<!--COPY--> cycle: {}", cycle.join(" -> ")),
+        ));
+    }
+    stack.push(id);
+    for dependency in dependencies.get(id).into_iter().flatten() {
+        visit_dependencies(dependency, dependencies, stack, done, order)?;
+    }
+    stack.pop();
+    done.insert(id);
+    order.push(id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{parse_document_async, tests::serialize_for_test};
+
+    #[tokio::test]
+    async fn test_copy() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<p><!--COPY summary capitalize--></p>
+<p id="summary" copy-source>a <dfn id="widget">widget</dfn> for <code>frobbing</code></p>
+<ul><li><!--COPY summary--><li><!--COPY summary id-suffix=-index--></ul>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        proc.apply()?;
+        assert_eq!(
+            serialize_for_test(&[document]),
+            r#"<!DOCTYPE html><html><head></head><body><p>A <dfn>widget</dfn> for <code>frobbing</code></p>
+<p id="summary">a <dfn id="widget">widget</dfn> for <code>frobbing</code></p>
+<ul><li>a <dfn>widget</dfn> for <code>frobbing</code></li><li>a <dfn id="widget-index">widget</dfn> for <code>frobbing</code></li></ul></body></html>"#
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nested_copy() -> io::Result<()> {
+        // The outer source is expanded before it is copied, wherever it is.
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<p><!--COPY outer--></p>
+<div id="outer" copy-source>[<!--COPY inner-->]</div>
+<span id="inner" copy-source>x</span>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        proc.apply()?;
+        assert_eq!(
+            serialize_for_test(&[document]),
+            r#"<!DOCTYPE html><html><head></head><body><p>[x]</p>
+<div id="outer">[x]</div>
+<span id="inner">x</span></body></html>"#
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cycle() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<div id="a" copy-source><!--COPY b--></div>
+<div id="b" copy-source><span><!--COPY a--></span></div>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let error = proc.apply().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert!(error.to_string().contains("a -> b -> a"), "{error}");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_errors() -> io::Result<()> {
+        let parsed = parse_document_async(
+            r#"<!DOCTYPE html>
+<p><!--COPY nowhere--></p>
+<p copy-source>no ID</p>
+<p id="x" copy-source>first</p>
+<p id="x" copy-source>second</p>
+<p><!--COPY x shout--></p>
+<p><!--COPY x id-suffix=--></p>"#
+                .as_bytes(),
+        )
+        .await?;
+        let document = parsed.document().clone();
+        let mut proc = Processor::new(&parsed);
+        dom_utils::scan_dom(&document, &mut |h| proc.visit(h));
+        let error = proc.apply().unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        let message = error.to_string();
+        assert!(message.contains("Line 2: <!--COPY nowhere-->"), "{message}");
+        assert!(
+            message.contains("Line 3: copy-source element has no ID"),
+            "{message}"
+        );
+        assert!(
+            message.contains("Line 5: copy-source ID \"x\""),
+            "{message}"
+        );
+        assert!(
+            message.contains("Line 6: <!--COPY x shout--> has unknown option"),
+            "{message}"
+        );
+        assert!(
+            message.contains("Line 7: <!--COPY x id-suffix=--> has an empty id-suffix."),
+            "{message}"
+        );
+        Ok(())
+    }
+}
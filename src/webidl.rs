@@ -0,0 +1,891 @@
+//! A parser for Web IDL, as used in the spec's <code class="idl"> blocks.
+//!
+//! This follows the grammar closely enough to report syntax errors, but only
+//! keeps what the checks and indexes need: the definitions, their members and
+//! extended attributes, and the named types they refer to. Line numbers are
+//! relative to the start of the parsed text, starting at 1.
+
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DefinitionKind {
+    Interface,
+    InterfaceMixin,
+    CallbackInterface,
+    CallbackFunction,
+    Dictionary,
+    Enum,
+    Typedef,
+    Namespace,
+
+    /// An "A includes B;" statement.
+    Includes,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExtendedAttribute {
+    pub name: String,
+
+    /// The identifier(s) after "=", e.g., ["Window", "Worker"] for
+    /// [Exposed=(Window,Worker)].
+    pub values: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MemberKind {
+    Const,
+    Attribute,
+    Operation,
+    Constructor,
+    Stringifier,
+    Iterable,
+    Maplike,
+    Setlike,
+
+    /// A dictionary member.
+    Field,
+
+    /// An enumeration value.
+    Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Member {
+    pub kind: MemberKind,
+
+    /// The identifier, if the member has one (e.g., unnamed getters don't).
+    pub name: Option<String>,
+
+    /// For attributes, the first named type in their type, e.g., EventHandler.
+    pub type_name: Option<String>,
+    pub is_static: bool,
+    pub extended_attributes: Vec<ExtendedAttribute>,
+    pub line: u64,
+}
+
+/// A use of a named type, i.e., not a built-in one like DOMString.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeReference {
+    pub name: String,
+    pub line: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Definition {
+    pub kind: DefinitionKind,
+
+    /// The identifier being defined; for includes statements, the interface
+    /// which includes the mixin.
+    pub name: String,
+
+    /// The offset of the name's first character in the parsed text, so that it
+    /// can be matched up with the markup around it.
+    pub name_offset: usize,
+    pub partial: bool,
+
+    /// The inherited interface or dictionary.
+    pub inherits: Option<String>,
+
+    /// For includes statements, the mixin.
+    pub includes: Option<String>,
+
+    /// For includes statements, the offset of the mixin's first character.
+    pub includes_offset: Option<usize>,
+    pub extended_attributes: Vec<ExtendedAttribute>,
+    pub members: Vec<Member>,
+
+    /// The named types used by the definition and its members.
+    pub references: Vec<TypeReference>,
+    pub line: u64,
+}
+
+impl Definition {
+    pub fn extended_attribute(&self, name: &str) -> Option<&ExtendedAttribute> {
+        self.extended_attributes.iter().find(|ea| ea.name == name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    pub line: u64,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Types which are built into Web IDL, and so needn't be defined anywhere.
+const BUILTIN_TYPES: &[&str] = &[
+    "any",
+    "undefined",
+    "boolean",
+    "byte",
+    "octet",
+    "short",
+    "long",
+    "float",
+    "double",
+    "bigint",
+    "DOMString",
+    "ByteString",
+    "USVString",
+    "object",
+    "symbol",
+    "ArrayBuffer",
+    "SharedArrayBuffer",
+    "DataView",
+    "Int8Array",
+    "Int16Array",
+    "Int32Array",
+    "Uint8Array",
+    "Uint16Array",
+    "Uint32Array",
+    "Uint8ClampedArray",
+    "BigInt64Array",
+    "BigUint64Array",
+    "Float16Array",
+    "Float32Array",
+    "Float64Array",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Identifier,
+    String,
+    Number,
+    Other,
+}
+
+#[derive(Clone, Debug)]
+struct Token {
+    kind: TokenKind,
+    text: String,
+    line: u64,
+
+    /// The offset of the token's first character in the text.
+    offset: usize,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, ParseError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = text.chars().collect();
+    let mut line = 1;
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c == '\n' {
+            line += 1;
+            i += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'/') {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if c == '/' && chars.get(i + 1) == Some(&'*') {
+            i += 2;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(ParseError {
+                            line,
+                            message: "Unterminated comment.".to_owned(),
+                        });
+                    }
+                    Some('*') if chars.get(i + 1) == Some(&'/') => break,
+                    Some('\n') => line += 1,
+                    _ => (),
+                }
+                i += 1;
+            }
+            i += 2;
+            continue;
+        }
+        let kind = if c == '"' {
+            i += 1;
+            while i < chars.len() && chars[i] != '"' {
+                if chars[i] == '\n' {
+                    return Err(ParseError {
+                        line,
+                        message: "Unterminated string.".to_owned(),
+                    });
+                }
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(ParseError {
+                    line,
+                    message: "Unterminated string.".to_owned(),
+                });
+            }
+            i += 1;
+            TokenKind::String
+        } else if c.is_ascii_digit()
+            || ((c == '-' || c == '.') && chars.get(i + 1).is_some_and(|n| n.is_ascii_digit()))
+        {
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric()
+                    || chars[i] == '.'
+                    || ((chars[i] == '-' || chars[i] == '+') && matches!(chars[i - 1], 'e' | 'E')))
+            {
+                i += 1;
+            }
+            TokenKind::Number
+        } else if c.is_ascii_alphabetic()
+            || ((c == '_' || c == '-') && chars.get(i + 1).is_some_and(|n| n.is_ascii_alphabetic()))
+        {
+            i += 1;
+            while i < chars.len()
+                && (chars[i].is_ascii_alphanumeric() || chars[i] == '_' || chars[i] == '-')
+            {
+                i += 1;
+            }
+            TokenKind::Identifier
+        } else if text_at(&chars, i, "...") {
+            i += 3;
+            TokenKind::Other
+        } else {
+            i += 1;
+            TokenKind::Other
+        };
+        tokens.push(Token {
+            kind,
+            text: chars[start..i].iter().collect(),
+            line,
+            offset: start,
+        });
+    }
+    Ok(tokens)
+}
+
+fn text_at(chars: &[char], i: usize, expected: &str) -> bool {
+    expected
+        .chars()
+        .enumerate()
+        .all(|(j, e)| chars.get(i + j) == Some(&e))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+
+    /// The named types referenced since the start of the current definition.
+    references: Vec<TypeReference>,
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn peek_is(&self, text: &str) -> bool {
+        self.peek()
+            .is_some_and(|t| t.kind != TokenKind::String && t.text == text)
+    }
+
+    fn line(&self) -> u64 {
+        match self.peek() {
+            Some(token) => token.line,
+            None => self.tokens.last().map_or(1, |t| t.line),
+        }
+    }
+
+    fn offset(&self) -> usize {
+        self.peek().map_or(0, |t| t.offset)
+    }
+
+    fn error<T>(&self, expected: &str) -> ParseResult<T> {
+        let found = match self.peek() {
+            Some(token) => format!("\"{}\"", token.text),
+            None => "end of IDL".to_owned(),
+        };
+        Err(ParseError {
+            line: self.line(),
+            message: format!("Expected {expected}, found {found}."),
+        })
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.peek_is(text) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> ParseResult<()> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            self.error(&format!("\"{text}\""))
+        }
+    }
+
+    fn identifier(&mut self) -> ParseResult<String> {
+        match self.peek() {
+            Some(token) if token.kind == TokenKind::Identifier => {
+                let text = token.text.clone();
+                self.position += 1;
+                Ok(text)
+            }
+            _ => self.error("an identifier"),
+        }
+    }
+
+    /// Parses an optional "[...]" list.
+    fn extended_attributes(&mut self) -> ParseResult<Vec<ExtendedAttribute>> {
+        let mut result = Vec::new();
+        if !self.eat("[") {
+            return Ok(result);
+        }
+        loop {
+            let name = self.identifier()?;
+            let mut values = Vec::new();
+            if self.eat("=") {
+                if self.eat("(") {
+                    loop {
+                        values.push(self.extended_attribute_value()?);
+                        if !self.eat(",") {
+                            break;
+                        }
+                    }
+                    self.expect(")")?;
+                } else {
+                    values.push(self.extended_attribute_value()?);
+                }
+            }
+            if self.peek_is("(") {
+                self.arguments()?;
+            }
+            result.push(ExtendedAttribute { name, values });
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect("]")?;
+        Ok(result)
+    }
+
+    fn extended_attribute_value(&mut self) -> ParseResult<String> {
+        match self.peek() {
+            Some(token) if token.kind != TokenKind::Other || token.text == "*" => {
+                let text = token.text.trim_matches('"').to_owned();
+                self.position += 1;
+                Ok(text)
+            }
+            _ => self.error("an extended attribute value"),
+        }
+    }
+
+    /// Parses a type, recording any named types it uses.
+    fn type_(&mut self) -> ParseResult<()> {
+        self.extended_attributes()?;
+        if self.eat("(") {
+            loop {
+                self.type_()?;
+                if !self.eat("or") {
+                    break;
+                }
+            }
+            self.expect(")")?;
+        } else {
+            let line = self.line();
+            let name = self.identifier()?;
+            match name.as_str() {
+                "sequence" | "FrozenArray" | "ObservableArray" | "Promise" => {
+                    self.expect("<")?;
+                    self.type_()?;
+                    self.expect(">")?;
+                }
+                "record" => {
+                    self.expect("<")?;
+                    self.type_()?;
+                    self.expect(",")?;
+                    self.type_()?;
+                    self.expect(">")?;
+                }
+                "unsigned" => {
+                    if !self.eat("short") {
+                        self.expect("long")?;
+                        self.eat("long");
+                    }
+                }
+                "unrestricted" => {
+                    if !self.eat("float") {
+                        self.expect("double")?;
+                    }
+                }
+                "long" => {
+                    self.eat("long");
+                }
+                _ if BUILTIN_TYPES.contains(&name.as_str()) => (),
+                _ => self.references.push(TypeReference { name, line }),
+            }
+        }
+        self.eat("?");
+        Ok(())
+    }
+
+    fn default_value(&mut self) -> ParseResult<()> {
+        if self.eat("[") {
+            return self.expect("]");
+        }
+        if self.eat("{") {
+            return self.expect("}");
+        }
+        match self.peek() {
+            Some(token) if token.kind != TokenKind::Other => {
+                self.position += 1;
+                Ok(())
+            }
+            _ => self.error("a default value"),
+        }
+    }
+
+    fn arguments(&mut self) -> ParseResult<()> {
+        self.expect("(")?;
+        if self.eat(")") {
+            return Ok(());
+        }
+        loop {
+            self.extended_attributes()?;
+            let optional = self.eat("optional");
+            self.type_()?;
+            self.eat("...");
+            self.identifier()?;
+            if optional && self.eat("=") {
+                self.default_value()?;
+            }
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(")")
+    }
+
+    /// Parses a member of an interface, mixin, callback interface or
+    /// namespace.
+    fn member(&mut self) -> ParseResult<Member> {
+        let line = self.line();
+        let extended_attributes = self.extended_attributes()?;
+        let mut member = Member {
+            kind: MemberKind::Operation,
+            name: None,
+            type_name: None,
+            is_static: false,
+            extended_attributes,
+            line,
+        };
+        if self.eat("const") {
+            self.type_()?;
+            member.kind = MemberKind::Const;
+            member.name = Some(self.identifier()?);
+            self.expect("=")?;
+            self.default_value()?;
+        } else if self.eat("constructor") {
+            member.kind = MemberKind::Constructor;
+            self.arguments()?;
+        } else if self.eat("stringifier") && self.peek_is(";") {
+            member.kind = MemberKind::Stringifier;
+        } else {
+            member.is_static = self.eat("static");
+            let is_async = self.eat("async");
+            let readonly = self.eat("readonly");
+            if self.eat("iterable") {
+                member.kind = MemberKind::Iterable;
+                self.type_parameters()?;
+                if is_async && self.peek_is("(") {
+                    self.arguments()?;
+                }
+            } else if is_async {
+                return self.error("\"iterable\"");
+            } else if self.eat("maplike") {
+                member.kind = MemberKind::Maplike;
+                self.type_parameters()?;
+            } else if self.eat("setlike") {
+                member.kind = MemberKind::Setlike;
+                self.type_parameters()?;
+            } else if readonly || self.peek_is("attribute") || self.peek_is("inherit") {
+                self.eat("inherit");
+                self.expect("attribute")?;
+                member.kind = MemberKind::Attribute;
+                let first_reference = self.references.len();
+                self.type_()?;
+                member.type_name = self.references.get(first_reference).map(|r| r.name.clone());
+                member.name = Some(self.identifier()?);
+            } else {
+                while self.eat("getter") || self.eat("setter") || self.eat("deleter") {}
+                self.type_()?;
+                if !self.peek_is("(") {
+                    member.name = Some(self.identifier()?);
+                }
+                self.arguments()?;
+            }
+        }
+        self.expect(";")?;
+        Ok(member)
+    }
+
+    /// Parses e.g. "<DOMString, long>".
+    fn type_parameters(&mut self) -> ParseResult<()> {
+        self.expect("<")?;
+        loop {
+            self.type_()?;
+            if !self.eat(",") {
+                break;
+            }
+        }
+        self.expect(">")
+    }
+
+    fn dictionary_member(&mut self) -> ParseResult<Member> {
+        let line = self.line();
+        let extended_attributes = self.extended_attributes()?;
+        self.eat("required");
+        self.type_()?;
+        let name = Some(self.identifier()?);
+        if self.eat("=") {
+            self.default_value()?;
+        }
+        self.expect(";")?;
+        Ok(Member {
+            kind: MemberKind::Field,
+            name,
+            type_name: None,
+            is_static: false,
+            extended_attributes,
+            line,
+        })
+    }
+
+    fn enum_values(&mut self) -> ParseResult<Vec<Member>> {
+        let mut values = Vec::new();
+        while let Some(token) = self.peek().filter(|t| t.kind == TokenKind::String) {
+            values.push(Member {
+                kind: MemberKind::Value,
+                name: Some(token.text.trim_matches('"').to_owned()),
+                type_name: None,
+                is_static: false,
+                extended_attributes: Vec::new(),
+                line: token.line,
+            });
+            self.position += 1;
+            if !self.eat(",") {
+                break;
+            }
+        }
+        if values.is_empty() {
+            return self.error("a string");
+        }
+        Ok(values)
+    }
+
+    /// Parses "{ members };".
+    fn body(&mut self, kind: DefinitionKind) -> ParseResult<Vec<Member>> {
+        self.expect("{")?;
+        let mut members = Vec::new();
+        if kind == DefinitionKind::Enum {
+            members = self.enum_values()?;
+        } else {
+            while !self.peek_is("}") {
+                if self.peek().is_none() {
+                    return self.error("\"}\"");
+                }
+                members.push(match kind {
+                    DefinitionKind::Dictionary => self.dictionary_member()?,
+                    _ => self.member()?,
+                });
+            }
+        }
+        self.expect("}")?;
+        self.expect(";")?;
+        Ok(members)
+    }
+
+    fn definition(&mut self) -> ParseResult<Definition> {
+        let line = self.line();
+        self.references.clear();
+        let extended_attributes = self.extended_attributes()?;
+        let mut definition = Definition {
+            kind: DefinitionKind::Interface,
+            name: String::new(),
+            name_offset: 0,
+            partial: false,
+            inherits: None,
+            includes: None,
+            includes_offset: None,
+            extended_attributes,
+            members: Vec::new(),
+            references: Vec::new(),
+            line,
+        };
+        definition.partial = self.eat("partial");
+        if definition.partial
+            && !["interface", "dictionary", "namespace"]
+                .iter()
+                .any(|k| self.peek_is(k))
+        {
+            return self.error("\"interface\", \"dictionary\" or \"namespace\"");
+        }
+        definition.kind = if self.eat("callback") {
+            if self.eat("interface") {
+                DefinitionKind::CallbackInterface
+            } else {
+                DefinitionKind::CallbackFunction
+            }
+        } else if self.eat("interface") {
+            if self.eat("mixin") {
+                DefinitionKind::InterfaceMixin
+            } else {
+                DefinitionKind::Interface
+            }
+        } else if self.eat("dictionary") {
+            DefinitionKind::Dictionary
+        } else if self.eat("enum") {
+            DefinitionKind::Enum
+        } else if self.eat("namespace") {
+            DefinitionKind::Namespace
+        } else if self.eat("typedef") {
+            DefinitionKind::Typedef
+        } else if self.peek().is_some_and(|t| t.kind == TokenKind::Identifier) {
+            DefinitionKind::Includes
+        } else {
+            return self.error("a definition");
+        };
+
+        match definition.kind {
+            DefinitionKind::Typedef => {
+                self.type_()?;
+                definition.name_offset = self.offset();
+                definition.name = self.identifier()?;
+                self.expect(";")?;
+            }
+            DefinitionKind::Includes => {
+                definition.name_offset = self.offset();
+                definition.name = self.identifier()?;
+                self.expect("includes")?;
+                definition.includes_offset = Some(self.offset());
+                definition.includes = Some(self.identifier()?);
+                self.expect(";")?;
+            }
+            DefinitionKind::CallbackFunction => {
+                definition.name_offset = self.offset();
+                definition.name = self.identifier()?;
+                self.expect("=")?;
+                self.type_()?;
+                self.arguments()?;
+                self.expect(";")?;
+            }
+            kind => {
+                definition.name_offset = self.offset();
+                definition.name = self.identifier()?;
+                if !definition.partial
+                    && matches!(kind, DefinitionKind::Interface | DefinitionKind::Dictionary)
+                    && self.eat(":")
+                {
+                    definition.inherits = Some(self.identifier()?);
+                }
+                definition.members = self.body(kind)?;
+            }
+        }
+        definition.references = std::mem::take(&mut self.references);
+        Ok(definition)
+    }
+}
+
+/// Parses the IDL, returning its definitions in order, or the first syntax
+/// error.
+pub fn parse(text: &str) -> Result<Vec<Definition>, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        position: 0,
+        references: Vec::new(),
+    };
+    let mut definitions = Vec::new();
+    while parser.peek().is_some() {
+        definitions.push(parser.definition()?);
+    }
+    Ok(definitions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interface() {
+        let definitions = parse(
+            r#"[Exposed=(Window,Worker), LegacyFactoryFunction=Image(optional unsigned long width)]
+interface HTMLImageElement : HTMLElement {
+  [HTMLConstructor] constructor();
+
+  const unsigned short NONE = 0;
+  [CEReactions, Reflect] attribute DOMString alt;
+  readonly attribute boolean complete;
+  attribute (HTMLScriptElement or SVGScriptElement)? currentScript;
+  static Promise<undefined> decode(optional DecodeOptions options = {});
+  getter Element? (DOMString name);
+  stringifier;
+  iterable<DOMString, FormDataEntryValue>;
+  readonly maplike<DOMString, unrestricted double>;
+};"#,
+        )
+        .unwrap();
+        assert_eq!(definitions.len(), 1);
+        let definition = &definitions[0];
+        assert_eq!(definition.kind, DefinitionKind::Interface);
+        assert_eq!(definition.name, "HTMLImageElement");
+        assert_eq!(definition.inherits.as_deref(), Some("HTMLElement"));
+        assert_eq!(definition.line, 1);
+        assert_eq!(
+            definition.extended_attribute("Exposed").unwrap().values,
+            ["Window", "Worker"]
+        );
+        let members: Vec<_> = definition
+            .members
+            .iter()
+            .map(|m| (m.kind, m.name.as_deref(), m.is_static, m.line))
+            .collect();
+        assert_eq!(
+            members,
+            [
+                (MemberKind::Constructor, None, false, 3),
+                (MemberKind::Const, Some("NONE"), false, 5),
+                (MemberKind::Attribute, Some("alt"), false, 6),
+                (MemberKind::Attribute, Some("complete"), false, 7),
+                (MemberKind::Attribute, Some("currentScript"), false, 8),
+                (MemberKind::Operation, Some("decode"), true, 9),
+                (MemberKind::Operation, None, false, 10),
+                (MemberKind::Stringifier, None, false, 11),
+                (MemberKind::Iterable, None, false, 12),
+                (MemberKind::Maplike, None, false, 13),
+            ]
+        );
+        let type_names: Vec<_> = definition
+            .members
+            .iter()
+            .filter(|m| m.kind == MemberKind::Attribute)
+            .map(|m| m.type_name.as_deref())
+            .collect();
+        assert_eq!(type_names, [None, None, Some("HTMLScriptElement")]);
+        let references: Vec<_> = definition
+            .references
+            .iter()
+            .map(|r| (r.name.as_str(), r.line))
+            .collect();
+        assert_eq!(
+            references,
+            [
+                ("HTMLScriptElement", 8),
+                ("SVGScriptElement", 8),
+                ("DecodeOptions", 9),
+                ("Element", 10),
+                ("FormDataEntryValue", 12),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_other_definitions() {
+        let text = r#"// A comment.
+interface mixin WindowOrWorkerGlobalScope { readonly attribute boolean isSecureContext; };
+Window includes WindowOrWorkerGlobalScope;
+partial interface Document { attribute DOMString title; };
+dictionary ImageBitmapOptions : BaseOptions { required ImageOrientation imageOrientation = "from-image"; };
+enum ImageOrientation { "from-image", "flipY", };
+callback BlobCallback = undefined (Blob? blob);
+callback interface EventListener { undefined handleEvent(Event event); };
+/* Another
+   comment. */
+typedef (HTMLOptionElement or HTMLOptGroupElement) HTMLOptionOrOptGroupElement;
+[Exposed=Window] namespace console { undefined log(any... data); };"#;
+        let definitions = parse(text).unwrap();
+        let summary: Vec<_> = definitions
+            .iter()
+            .map(|d| (d.kind, d.name.as_str(), d.partial, d.line))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (
+                    DefinitionKind::InterfaceMixin,
+                    "WindowOrWorkerGlobalScope",
+                    false,
+                    2
+                ),
+                (DefinitionKind::Includes, "Window", false, 3),
+                (DefinitionKind::Interface, "Document", true, 4),
+                (DefinitionKind::Dictionary, "ImageBitmapOptions", false, 5),
+                (DefinitionKind::Enum, "ImageOrientation", false, 6),
+                (DefinitionKind::CallbackFunction, "BlobCallback", false, 7),
+                (DefinitionKind::CallbackInterface, "EventListener", false, 8),
+                (
+                    DefinitionKind::Typedef,
+                    "HTMLOptionOrOptGroupElement",
+                    false,
+                    11
+                ),
+                (DefinitionKind::Namespace, "console", false, 12),
+            ]
+        );
+        assert_eq!(
+            definitions[1].includes.as_deref(),
+            Some("WindowOrWorkerGlobalScope")
+        );
+        for definition in definitions.iter() {
+            assert!(text[definition.name_offset..].starts_with(&definition.name));
+        }
+        assert!(
+            text[definitions[1].includes_offset.unwrap()..]
+                .starts_with("WindowOrWorkerGlobalScope;")
+        );
+        assert_eq!(definitions[3].inherits.as_deref(), Some("BaseOptions"));
+        let values: Vec<_> = definitions[4]
+            .members
+            .iter()
+            .map(|m| m.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(values, ["from-image", "flipY"]);
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let error = |text| parse(text).unwrap_err();
+        assert_eq!(
+            error("interface A {\n  attribute DOMString;\n};"),
+            ParseError {
+                line: 2,
+                message: "Expected an identifier, found \";\".".to_owned()
+            }
+        );
+        assert_eq!(
+            error("interface A {\n  undefined f();\n"),
+            ParseError {
+                line: 2,
+                message: "Expected \"}\", found end of IDL.".to_owned()
+            }
+        );
+        assert_eq!(
+            error("partial enum E { \"a\" };"),
+            ParseError {
+                line: 1,
+                message: "Expected \"interface\", \"dictionary\" or \"namespace\", found \"enum\"."
+                    .to_owned()
+            }
+        );
+    }
+}